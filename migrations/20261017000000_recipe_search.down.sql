@@ -0,0 +1,3 @@
+DROP INDEX recipe_search_idx;
+
+ALTER TABLE recipe DROP COLUMN search;
@@ -0,0 +1,8 @@
+ALTER TABLE recipe ADD COLUMN search tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', name), 'A') ||
+    setweight(to_tsvector('english', ingredients), 'B') ||
+    setweight(to_tsvector('english', directions), 'C') ||
+    setweight(to_tsvector('english', notes), 'C')
+) STORED;
+
+CREATE INDEX recipe_search_idx ON recipe USING GIN (search);
@@ -0,0 +1 @@
+ALTER TABLE status DROP COLUMN last_synced_at;
@@ -0,0 +1 @@
+ALTER TABLE status ADD COLUMN last_synced_at TIMESTAMP WITH TIME ZONE;
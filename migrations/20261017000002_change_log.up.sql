@@ -0,0 +1,9 @@
+CREATE TABLE change_log (
+    id SERIAL PRIMARY KEY,
+    collection TEXT NOT NULL,
+    uid TEXT NOT NULL,
+    state TEXT NOT NULL,
+    changed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX change_log_changed_at_idx ON change_log (changed_at);
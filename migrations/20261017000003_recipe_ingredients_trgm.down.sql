@@ -0,0 +1 @@
+DROP INDEX recipe_ingredients_trgm_idx;
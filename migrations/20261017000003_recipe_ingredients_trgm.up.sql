@@ -0,0 +1,3 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX recipe_ingredients_trgm_idx ON recipe USING GIN (ingredients gin_trgm_ops);
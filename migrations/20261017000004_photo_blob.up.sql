@@ -0,0 +1,4 @@
+CREATE TABLE photo_blob (
+    hash TEXT PRIMARY KEY,
+    bytes BYTEA NOT NULL
+);
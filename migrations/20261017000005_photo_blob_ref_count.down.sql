@@ -0,0 +1 @@
+ALTER TABLE photo_blob DROP COLUMN ref_count;
@@ -0,0 +1 @@
+ALTER TABLE recipe DROP COLUMN cook_time_minutes;
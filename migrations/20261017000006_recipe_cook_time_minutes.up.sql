@@ -0,0 +1 @@
+ALTER TABLE recipe ADD COLUMN cook_time_minutes INTEGER;
@@ -0,0 +1 @@
+ALTER TABLE recipe DROP COLUMN nutritional_info;
@@ -0,0 +1 @@
+ALTER TABLE recipe ADD COLUMN nutritional_info TEXT;
@@ -0,0 +1 @@
+DROP TABLE skipped_recipe;
@@ -0,0 +1,4 @@
+CREATE TABLE skipped_recipe (
+    uid TEXT PRIMARY KEY,
+    hash TEXT NOT NULL
+);
@@ -0,0 +1,4 @@
+-- Recipes stored before durations were parsed need to be filled in once,
+-- while recipes stored from now on are parsed as they're saved.
+ALTER TABLE recipe ADD COLUMN durations_parsed BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE recipe ALTER COLUMN durations_parsed SET DEFAULT true;
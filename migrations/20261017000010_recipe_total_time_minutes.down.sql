@@ -0,0 +1 @@
+ALTER TABLE recipe DROP COLUMN total_time_minutes;
@@ -0,0 +1,4 @@
+ALTER TABLE recipe ADD COLUMN total_time_minutes INTEGER;
+
+-- Parse total times of existing recipes on the next startup.
+UPDATE recipe SET durations_parsed = false;
@@ -0,0 +1,5 @@
+ALTER TABLE photo_blob ADD COLUMN ref_count INTEGER NOT NULL DEFAULT 1;
+
+UPDATE photo_blob SET ref_count = greatest(1, (
+    SELECT count(*) FROM photo WHERE lower(photo.hash) = photo_blob.hash
+));
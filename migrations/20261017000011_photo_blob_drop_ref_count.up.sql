@@ -0,0 +1,3 @@
+-- Stored contents are now kept while any photo has their hash, so counts
+-- can't drift from the photos that use them.
+ALTER TABLE photo_blob DROP COLUMN ref_count;
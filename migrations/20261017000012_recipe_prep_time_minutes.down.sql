@@ -0,0 +1 @@
+ALTER TABLE recipe DROP COLUMN prep_time_minutes;
@@ -0,0 +1,4 @@
+ALTER TABLE recipe ADD COLUMN prep_time_minutes INTEGER;
+
+-- Parse prep times of existing recipes on the next startup.
+UPDATE recipe SET durations_parsed = false;
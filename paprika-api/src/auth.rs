@@ -0,0 +1,134 @@
+use std::{
+    collections::HashMap,
+    future::{ready, Ready},
+    sync::Arc,
+};
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    error::InternalError,
+    http::header,
+    web, Error, HttpResponse,
+};
+use futures::future::LocalBoxFuture;
+
+/// Middleware requiring requests to include a shared secret as a bearer
+/// token. When no secret is configured, all requests are allowed.
+#[derive(Clone)]
+pub struct BearerAuth {
+    secret: Option<Arc<str>>,
+    query_token: bool,
+}
+
+impl BearerAuth {
+    pub fn new(secret: Option<String>) -> Self {
+        Self {
+            secret: secret.map(Into::into),
+            query_token: false,
+        }
+    }
+
+    /// Also accept the secret in a `token` query parameter, for clients such
+    /// as calendar apps that can't send an `Authorization` header.
+    pub fn allow_query_token(mut self) -> Self {
+        self.query_token = true;
+        self
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for BearerAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = BearerAuthMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(BearerAuthMiddleware {
+            service,
+            secret: self.secret.clone(),
+            query_token: self.query_token,
+        }))
+    }
+}
+
+pub struct BearerAuthMiddleware<S> {
+    service: S,
+    secret: Option<Arc<str>>,
+    query_token: bool,
+}
+
+impl<S> BearerAuthMiddleware<S> {
+    fn is_authorized(&self, req: &ServiceRequest) -> bool {
+        let secret = match &self.secret {
+            Some(secret) => secret,
+            None => return true,
+        };
+
+        let header_token = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_string());
+
+        let token = header_token.or_else(|| {
+            if !self.query_token {
+                return None;
+            }
+
+            web::Query::<HashMap<String, String>>::from_query(req.query_string())
+                .ok()
+                .and_then(|query| query.into_inner().remove("token"))
+        });
+
+        token
+            .map(|token| constant_time_eq(token.as_bytes(), secret.as_bytes()))
+            .unwrap_or(false)
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for BearerAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if !self.is_authorized(&req) {
+            tracing::debug!("rejecting unauthorized request to {}", req.path());
+
+            let resp = HttpResponse::Unauthorized()
+                .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+                .finish();
+
+            return Box::pin(ready(Err(InternalError::from_response(
+                "unauthorized",
+                resp,
+            )
+            .into())));
+        }
+
+        Box::pin(self.service.call(req))
+    }
+}
+
+/// Compare two byte strings without exiting early on the first difference.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
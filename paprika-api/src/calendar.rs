@@ -0,0 +1,138 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+
+/// A planned meal with the details needed for a calendar event.
+pub struct CalendarMeal {
+    pub uid: String,
+    pub name: String,
+    pub date: DateTime<Utc>,
+    pub meal_type: Option<String>,
+    pub export_all_day: bool,
+    pub export_time: i32,
+}
+
+impl CalendarMeal {
+    /// Load meals planned on or after `from` and before `to`.
+    pub async fn in_range(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> sqlx::Result<Vec<Self>> {
+        let from = from.map(|from| DateTime::<Utc>::from_utc(start_of_day(from), Utc));
+        let to = to.map(|to| DateTime::<Utc>::from_utc(start_of_day(to), Utc));
+
+        sqlx::query_as!(
+            Self,
+            r#"SELECT
+                meal.uid,
+                coalesce(recipe.name, meal.name) "name!",
+                meal.date,
+                meal_type.name "meal_type?",
+                coalesce(meal_type.export_all_day, true) "export_all_day!",
+                coalesce(meal_type.export_time, 0) "export_time!"
+            FROM
+                meal
+            LEFT JOIN recipe
+                ON recipe.uid = meal.recipe_uid
+            LEFT JOIN meal_type
+                ON meal_type.uid = meal.type_uid
+            WHERE
+                ($1::timestamptz IS NULL OR meal.date >= $1)
+                AND ($2::timestamptz IS NULL OR meal.date < $2)
+            ORDER BY
+                meal.date, meal.order_flag"#,
+            from,
+            to
+        )
+        .fetch_all(pool)
+        .await
+    }
+}
+
+/// Render meals as an iCalendar feed.
+///
+/// Meals are all-day events unless their meal type has an export time, in
+/// which case they start that many seconds after the start of the day and
+/// last an hour. Paprika dates have no time zone, so timed events use
+/// floating local times.
+pub fn render(meals: &[CalendarMeal], now: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//paprika-rs//Meal Plan//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "X-WR-CALNAME:Meal Plan".to_string(),
+    ];
+
+    let stamp = now.format("%Y%m%dT%H%M%SZ");
+
+    for meal in meals {
+        let day = meal.date.date_naive();
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}@paprika-rs", escape_text(&meal.uid)));
+        lines.push(format!("DTSTAMP:{}", stamp));
+
+        if meal.export_all_day {
+            lines.push(format!("DTSTART;VALUE=DATE:{}", day.format("%Y%m%d")));
+            lines.push(format!(
+                "DTEND;VALUE=DATE:{}",
+                (day + Duration::days(1)).format("%Y%m%d")
+            ));
+        } else {
+            let start = start_of_day(day) + Duration::seconds(meal.export_time.into());
+            lines.push(format!("DTSTART:{}", start.format("%Y%m%dT%H%M%S")));
+            lines.push(format!(
+                "DTEND:{}",
+                (start + Duration::hours(1)).format("%Y%m%dT%H%M%S")
+            ));
+        }
+
+        lines.push(format!("SUMMARY:{}", escape_text(&meal.name)));
+        if let Some(meal_type) = &meal.meal_type {
+            lines.push(format!("DESCRIPTION:{}", escape_text(meal_type)));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    lines
+        .iter()
+        .map(|line| fold_line(line))
+        .collect::<Vec<_>>()
+        .join("")
+}
+
+fn start_of_day(date: NaiveDate) -> NaiveDateTime {
+    date.and_hms_opt(0, 0, 0)
+        .expect("midnight should always be valid")
+}
+
+/// Escape characters with special meaning in iCalendar text values.
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Split a content line so no line is longer than 75 bytes, ending each line
+/// with CRLF.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut line_len = 0;
+
+    for c in line.chars() {
+        if line_len + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            line_len = 1;
+        }
+
+        folded.push(c);
+        line_len += c.len_utf8();
+    }
+
+    folded.push_str("\r\n");
+    folded
+}
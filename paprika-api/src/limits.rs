@@ -0,0 +1,474 @@
+use std::{
+    collections::{HashMap, HashSet},
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    error::InternalError,
+    http::{header, Method},
+    web, Error, HttpResponse,
+};
+use futures::future::LocalBoxFuture;
+use juniper::{parser::parse_document_source, Definition, Selection};
+
+use crate::Schema;
+
+/// Maximum nesting depth and number of fields allowed in a GraphQL query, and
+/// if introspection queries are allowed.
+#[derive(Clone, Copy, Debug)]
+pub struct QueryLimits {
+    pub max_depth: usize,
+    pub max_fields: usize,
+    pub introspection: bool,
+}
+
+impl QueryLimits {
+    /// Load limits from `GRAPHQL_MAX_DEPTH` and `GRAPHQL_MAX_FIELDS`, using
+    /// defaults that allow the introspection queries used by GraphiQL.
+    /// Introspection is allowed unless `GRAPHQL_INTROSPECTION` is `false`.
+    pub fn from_env() -> Self {
+        let limit = |name: &str, default: usize| {
+            std::env::var(name)
+                .ok()
+                .map(|value| {
+                    value
+                        .parse()
+                        .unwrap_or_else(|_| panic!("{} must be a number", name))
+                })
+                .unwrap_or(default)
+        };
+
+        Self {
+            max_depth: limit("GRAPHQL_MAX_DEPTH", 20),
+            max_fields: limit("GRAPHQL_MAX_FIELDS", 1000),
+            introspection: !matches!(
+                std::env::var("GRAPHQL_INTROSPECTION").as_deref(),
+                Ok("0") | Ok("false")
+            ),
+        }
+    }
+
+    /// Check a query document against the limits, returning a message
+    /// explaining why it was rejected. The document is parsed with juniper's
+    /// parser, so it's understood exactly as it will be executed.
+    pub fn check(&self, schema: &Schema, query: &str) -> Result<(), String> {
+        let document = parse_document_source(query, &schema.schema)
+            .map_err(|err| format!("could not parse query: {}", err))?;
+
+        let mut operations = Vec::new();
+        let mut fragments = HashMap::new();
+        for definition in &document {
+            match definition {
+                Definition::Operation(operation) => {
+                    operations.push(operation.item.selection_set.as_slice())
+                }
+                Definition::Fragment(fragment) => {
+                    fragments.insert(
+                        fragment.item.name.item,
+                        fragment.item.selection_set.as_slice(),
+                    );
+                }
+            }
+        }
+
+        if !self.introspection
+            && operations
+                .iter()
+                .chain(fragments.values())
+                .any(|selections| introspects(selections))
+        {
+            return Err("introspection is disabled".to_string());
+        }
+
+        let mut depth = 0;
+        let mut fields: usize = 0;
+        let mut measured = HashMap::new();
+
+        for selections in operations {
+            let (operation_depth, operation_fields) =
+                measure(selections, &fragments, &mut HashSet::new(), &mut measured)?;
+            depth = depth.max(operation_depth);
+            fields = fields.saturating_add(operation_fields);
+        }
+
+        if depth > self.max_depth {
+            return Err(format!(
+                "query depth of {} exceeds limit of {}",
+                depth, self.max_depth
+            ));
+        }
+
+        if fields > self.max_fields {
+            return Err(format!(
+                "query has {} fields, exceeding limit of {}",
+                fields, self.max_fields
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// If the selections include the `__schema` or `__type` introspection
+/// fields at any depth.
+fn introspects(selections: &[Selection]) -> bool {
+    selections.iter().any(|selection| match selection {
+        Selection::Field(field) => {
+            matches!(field.item.name.item, "__schema" | "__type")
+                || field
+                    .item
+                    .selection_set
+                    .as_deref()
+                    .map(introspects)
+                    .unwrap_or(false)
+        }
+        Selection::InlineFragment(fragment) => introspects(&fragment.item.selection_set),
+        Selection::FragmentSpread(_) => false,
+    })
+}
+
+/// The depth and number of fields in a selection set, with fragments
+/// expanded. Each fragment is only measured once, so fragments spread many
+/// times can't make measuring a query expensive.
+fn measure<'a>(
+    selections: &'a [Selection<'a>],
+    fragments: &HashMap<&'a str, &'a [Selection<'a>]>,
+    visiting: &mut HashSet<&'a str>,
+    measured: &mut HashMap<&'a str, (usize, usize)>,
+) -> Result<(usize, usize), String> {
+    let mut depth = 0;
+    let mut fields: usize = 0;
+
+    for selection in selections {
+        let (selection_depth, selection_fields) = match selection {
+            Selection::Field(field) => {
+                let children = field.item.selection_set.as_deref().unwrap_or_default();
+                let (child_depth, child_fields) = measure(children, fragments, visiting, measured)?;
+                (child_depth + 1, child_fields.saturating_add(1))
+            }
+            Selection::InlineFragment(fragment) => {
+                measure(&fragment.item.selection_set, fragments, visiting, measured)?
+            }
+            Selection::FragmentSpread(spread) => {
+                let name = spread.item.name.item;
+
+                if let Some(size) = measured.get(name) {
+                    *size
+                } else {
+                    let fragment = fragments
+                        .get(name)
+                        .ok_or_else(|| format!("unknown fragment {}", name))?;
+
+                    if !visiting.insert(name) {
+                        return Err(format!("fragment {} references itself", name));
+                    }
+                    let size = measure(fragment, fragments, visiting, measured)?;
+                    visiting.remove(name);
+
+                    measured.insert(name, size);
+                    size
+                }
+            }
+        };
+
+        depth = depth.max(selection_depth);
+        fields = fields.saturating_add(selection_fields);
+    }
+
+    Ok((depth, fields))
+}
+
+/// Middleware rejecting GraphQL queries exceeding the configured limits
+/// before they are executed.
+#[derive(Clone, Copy)]
+pub struct LimitQueries {
+    limits: QueryLimits,
+}
+
+impl LimitQueries {
+    pub fn new(limits: QueryLimits) -> Self {
+        Self { limits }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for LimitQueries
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = LimitQueriesMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(LimitQueriesMiddleware {
+            service: Rc::new(service),
+            limits: self.limits,
+        }))
+    }
+}
+
+pub struct LimitQueriesMiddleware<S> {
+    service: Rc<S>,
+    limits: QueryLimits,
+}
+
+/// Find the queries in a request, from the query string for GET requests or
+/// the body otherwise. Bodies may contain a single request, a batch of
+/// requests, or a bare query.
+fn request_queries(req: &ServiceRequest, body: &[u8]) -> Vec<String> {
+    if req.method() == Method::GET {
+        return web::Query::<HashMap<String, String>>::from_query(req.query_string())
+            .ok()
+            .and_then(|query| query.get("query").cloned())
+            .into_iter()
+            .collect();
+    }
+
+    let is_graphql = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(|content_type| content_type.starts_with("application/graphql"))
+        .unwrap_or(false);
+
+    if is_graphql {
+        return vec![String::from_utf8_lossy(body).into_owned()];
+    }
+
+    let value: serde_json::Value = match serde_json::from_slice(body) {
+        Ok(value) => value,
+        Err(_) => return Vec::new(),
+    };
+
+    let requests = match value {
+        serde_json::Value::Array(requests) => requests,
+        request => vec![request],
+    };
+
+    requests
+        .iter()
+        .filter_map(|request| request.get("query")?.as_str())
+        .map(ToString::to_string)
+        .collect()
+}
+
+impl<S, B> Service<ServiceRequest> for LimitQueriesMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let limits = self.limits;
+        let schema = req.app_data::<web::Data<Schema>>().cloned();
+
+        Box::pin(async move {
+            let schema = schema.ok_or_else(|| {
+                actix_web::error::ErrorInternalServerError("schema was not configured")
+            })?;
+
+            let body = if req.method() == Method::GET {
+                web::Bytes::new()
+            } else {
+                let body = req.extract::<web::Bytes>().await?;
+                req.set_payload(body.clone().into());
+                body
+            };
+
+            for query in request_queries(&req, &body) {
+                if let Err(message) = limits.check(&schema, &query) {
+                    tracing::debug!("rejecting query: {}", message);
+
+                    let resp = HttpResponse::BadRequest().json(serde_json::json!({
+                        "errors": [{ "message": message }],
+                    }));
+
+                    return Err(InternalError::from_response("query too complex", resp).into());
+                }
+            }
+
+            service.call(req).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+    use crate::{Mutation, Query, Subscription};
+
+    fn schema() -> Schema {
+        Schema::new(Query, Mutation, Subscription)
+    }
+
+    fn limits(max_depth: usize, max_fields: usize) -> QueryLimits {
+        QueryLimits {
+            max_depth,
+            max_fields,
+            introspection: true,
+        }
+    }
+
+    #[test]
+    fn test_fragment_cycles() {
+        let schema = schema();
+        let query = r#"
+            fragment A on Recipe { name categories { ...B } }
+            fragment B on Category { name recipes { ...A } }
+            { recipes { ...A } }
+        "#;
+
+        let err = limits(100, 100).check(&schema, query).unwrap_err();
+        assert!(err.contains("references itself"), "{}", err);
+
+        let err = limits(100, 100)
+            .check(&schema, "{ recipes { ...Missing } }")
+            .unwrap_err();
+        assert_eq!(err, "unknown fragment Missing");
+    }
+
+    #[test]
+    fn test_repeated_fragment_spreads() {
+        let schema = schema();
+        let query = r#"
+            fragment Names on Recipe { name ingredients }
+            { a: recipes { ...Names ...Names } b: recipes { ...Names } }
+        "#;
+
+        // Each spread counts its fields again.
+        assert!(limits(2, 8).check(&schema, query).is_ok());
+        assert_eq!(
+            limits(2, 7).check(&schema, query).unwrap_err(),
+            "query has 8 fields, exceeding limit of 7"
+        );
+        assert_eq!(
+            limits(1, 8).check(&schema, query).unwrap_err(),
+            "query depth of 2 exceeds limit of 1"
+        );
+    }
+
+    #[test]
+    fn test_aliases() {
+        let schema = schema();
+        let query = "{ first: recipes { title: name } second: recipes { title: name } }";
+
+        assert!(limits(2, 4).check(&schema, query).is_ok());
+        assert!(limits(2, 3).check(&schema, query).is_err());
+        assert!(limits(1, 4).check(&schema, query).is_err());
+    }
+
+    #[test]
+    fn test_directives() {
+        let schema = schema();
+        let query = r#"
+            query ($names: Boolean!) {
+                recipes @include(if: true) {
+                    name @skip(if: $names)
+                    ... @include(if: $names) { ingredients }
+                }
+            }
+        "#;
+
+        assert!(limits(2, 3).check(&schema, query).is_ok());
+        assert!(limits(2, 2).check(&schema, query).is_err());
+    }
+
+    #[test]
+    fn test_block_strings() {
+        let schema = schema();
+
+        // Braces and spreads in strings are not part of the selection set.
+        let query = r#"{ searchRecipes(query: "{ { { \"quoted\" ...F") { name } }"#;
+        assert!(limits(2, 2).check(&schema, query).is_ok());
+        assert!(limits(1, 2).check(&schema, query).is_err());
+
+        // juniper's parser doesn't support block strings, so they are
+        // rejected the same way they would be when executed.
+        let query = r#"
+            {
+                searchRecipes(query: """
+                    { { { "quoted" \""" fragment F on Recipe { ...F }
+                """) { name }
+            }
+        "#;
+        let err = limits(100, 100).check(&schema, query).unwrap_err();
+        assert!(err.starts_with("could not parse query"), "{}", err);
+    }
+
+    #[test]
+    fn test_introspection() {
+        let schema = schema();
+        let disabled = QueryLimits {
+            introspection: false,
+            ..limits(100, 100)
+        };
+
+        let queries = [
+            "{ __schema { types { name } } }",
+            r#"{ recipes { name } __type(name: "Recipe") { name } }"#,
+            r#"fragment Types on Query { __type(name: "Recipe") { name } } { ...Types }"#,
+            "{ ... on Query { __schema { queryType { name } } } }",
+        ];
+        for query in queries {
+            assert!(limits(100, 100).check(&schema, query).is_ok(), "{}", query);
+            assert_eq!(
+                disabled.check(&schema, query).unwrap_err(),
+                "introspection is disabled",
+                "{}",
+                query
+            );
+        }
+
+        assert!(disabled
+            .check(&schema, "{ recipes { __typename name } }")
+            .is_ok());
+    }
+
+    #[test]
+    fn test_request_queries() {
+        let req = TestRequest::get()
+            .uri("/graphql?query=%7B%20recipes%20%7B%20name%20%7D%20%7D")
+            .to_srv_request();
+        assert_eq!(request_queries(&req, &[]), vec!["{ recipes { name } }"]);
+
+        let req = TestRequest::post()
+            .insert_header((header::CONTENT_TYPE, "application/graphql"))
+            .to_srv_request();
+        assert_eq!(
+            request_queries(&req, b"{ recipes { name } }"),
+            vec!["{ recipes { name } }"]
+        );
+
+        let req = TestRequest::post()
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .to_srv_request();
+        assert_eq!(
+            request_queries(&req, br#"{"query": "{ recipes { name } }"}"#),
+            vec!["{ recipes { name } }"]
+        );
+
+        let body = br#"[
+            {"query": "{ recipes { name } }"},
+            {"query": "{ menus { name } }", "variables": {}}
+        ]"#;
+        assert_eq!(
+            request_queries(&req, body),
+            vec!["{ recipes { name } }", "{ menus { name } }"]
+        );
+    }
+}
@@ -1,16 +1,89 @@
-use std::sync::Arc;
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use actix_cors::Cors;
 use actix_web::{http::header, web, App, Error, HttpRequest, HttpResponse, HttpServer};
+use base64::Engine;
 use dataloader::{cached::Loader, BatchFn};
-use juniper::{
-    graphql_object, graphql_value, EmptySubscription, FieldError, GraphQLObject, RootNode,
-};
+use juniper::{graphql_object, graphql_subscription, FieldError, GraphQLObject, RootNode};
 use juniper_actix::{graphiql_handler, graphql_handler, playground_handler};
+use juniper_graphql_ws::ConnectionConfig;
 use paprika_client::PaprikaClient;
 use updates::State;
 
+mod auth;
+mod calendar;
+mod limits;
+mod request_id;
+mod subscriptions;
 mod updates;
+mod webhook;
+
+/// Maximum number of database connections, unless set by
+/// `DATABASE_MAX_CONNECTIONS`.
+const DEFAULT_MAX_CONNECTIONS: u32 = 20;
+
+/// Number of idle database connections to keep open, unless set by
+/// `DATABASE_MIN_CONNECTIONS`.
+const DEFAULT_MIN_CONNECTIONS: u32 = 1;
+
+/// How long to keep retrying the initial database connection, unless set by
+/// `DATABASE_CONNECT_TIMEOUT_SECS`.
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 60;
+
+/// Longest delay between attempts to connect to the database.
+const MAX_CONNECT_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Connect to the database, retrying with exponential backoff until the
+/// timeout has passed so the server can start before the database is ready.
+async fn connect_with_retry<F>(
+    options: F,
+    url: &str,
+    timeout: Duration,
+) -> Result<sqlx::Pool<sqlx::Postgres>, sqlx::Error>
+where
+    F: Fn() -> sqlx::postgres::PgPoolOptions,
+{
+    let started = std::time::Instant::now();
+    let mut backoff = Duration::from_millis(500);
+    let mut attempt = 1;
+
+    loop {
+        match options().connect(url).await {
+            Ok(pool) => return Ok(pool),
+            Err(err) if started.elapsed() + backoff < timeout => {
+                tracing::warn!(
+                    "could not connect to database on attempt {}, retrying in {:?}: {}",
+                    attempt,
+                    backoff,
+                    err
+                );
+
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_CONNECT_BACKOFF);
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Parse a number from an environment variable, using the default if it is
+/// not set.
+fn env_number<T: std::str::FromStr>(name: &str, default: T) -> T {
+    match std::env::var(name) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("{} must be a number", name)),
+        Err(_) => default,
+    }
+}
 
 #[actix_web::main]
 async fn main() {
@@ -22,10 +95,25 @@ async fn main() {
         .await
         .expect("paprika token must be valid");
 
-    let pool = sqlx::postgres::PgPoolOptions::default()
-        .connect(&std::env::var("DATABASE_URL").unwrap())
-        .await
-        .unwrap();
+    let max_connections = env_number("DATABASE_MAX_CONNECTIONS", DEFAULT_MAX_CONNECTIONS);
+    let min_connections = env_number("DATABASE_MIN_CONNECTIONS", DEFAULT_MIN_CONNECTIONS);
+
+    let connect_timeout = Duration::from_secs(env_number(
+        "DATABASE_CONNECT_TIMEOUT_SECS",
+        DEFAULT_CONNECT_TIMEOUT_SECS,
+    ));
+
+    let pool = connect_with_retry(
+        || {
+            sqlx::postgres::PgPoolOptions::default()
+                .max_connections(max_connections)
+                .min_connections(min_connections.min(max_connections))
+        },
+        &std::env::var("DATABASE_URL").unwrap(),
+        connect_timeout,
+    )
+    .await
+    .expect("could not connect to database");
 
     tracing::info!("ensuring database is up to date");
 
@@ -34,53 +122,324 @@ async fn main() {
         .await
         .expect("could not run database migrations");
 
-    let paprika = Arc::new(paprika);
+    updates::backfill_durations(&pool)
+        .await
+        .expect("could not parse recipe durations");
+
+    let photo_dir = std::env::var("PHOTO_CACHE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| std::env::temp_dir().join("paprika-photos"));
+    std::fs::create_dir_all(&photo_dir).expect("could not create photo cache directory");
+
+    let conns = Connections {
+        pool,
+        paprika: Arc::new(paprika),
+        sync_lock: Default::default(),
+        sync_events: tokio::sync::broadcast::channel(SYNC_EVENTS_CAPACITY).0,
+        webhook: webhook::Webhook::from_env().expect("WEBHOOK_URL must be a valid url"),
+        shutdown: Default::default(),
+        photo_dir,
+    };
+    let shutdown = conns.shutdown.clone();
+
+    let sync_task = std::env::var("SYNC_INTERVAL_SECS")
+        .ok()
+        .map(|secs| {
+            secs.parse()
+                .expect("SYNC_INTERVAL_SECS must be a number of seconds")
+        })
+        .map(|secs| tokio::spawn(background_sync(conns.clone(), Duration::from_secs(secs))));
+
+    let api_secret = std::env::var("API_SECRET").ok();
+    if api_secret.is_none() {
+        let allow_unauthenticated = matches!(
+            std::env::var("ALLOW_UNAUTHENTICATED").as_deref(),
+            Ok("1") | Ok("true")
+        );
+        if !allow_unauthenticated {
+            panic!("API_SECRET must be set, or ALLOW_UNAUTHENTICATED=1 to run without it");
+        }
+
+        tracing::warn!("API_SECRET is not set, API is unauthenticated");
+    }
+    let auth = auth::BearerAuth::new(api_secret);
+
+    let allowed_origins: Option<Vec<String>> =
+        std::env::var("CORS_ALLOWED_ORIGINS").ok().map(|origins| {
+            origins
+                .split(',')
+                .map(|origin| origin.trim().to_string())
+                .filter(|origin| !origin.is_empty())
+                .collect()
+        });
+    if allowed_origins.is_none() {
+        tracing::warn!("CORS_ALLOWED_ORIGINS is not set, allowing requests from any origin");
+    }
+
+    let graphql_limits = limits::QueryLimits::from_env();
+    let query_limits = limits::LimitQueries::new(graphql_limits);
+
+    let enable_playground = matches!(
+        std::env::var("ENABLE_PLAYGROUND").as_deref(),
+        Ok("1") | Ok("true")
+    );
+    if enable_playground && !graphql_limits.introspection {
+        tracing::warn!("GRAPHQL_INTROSPECTION is disabled, not enabling playground");
+    }
+    let enable_playground = enable_playground && graphql_limits.introspection;
 
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         App::new()
-            .app_data(web::Data::new(Connections {
-                pool: pool.clone(),
-                paprika: paprika.clone(),
-            }))
-            .app_data(web::Data::new(Schema::new(
-                Query,
-                Mutation,
-                Default::default(),
-            )))
-            .wrap(
-                Cors::default()
-                    .allow_any_origin()
-                    .allowed_methods(vec!["POST", "GET"])
-                    .allowed_header(header::CONTENT_TYPE)
-                    .max_age(3600),
-            )
+            .app_data(web::Data::new(conns.clone()))
+            .app_data(web::Data::new(Schema::new(Query, Mutation, Subscription)))
+            .wrap(cors(allowed_origins.as_deref()))
+            .wrap(request_id::RequestId)
             .service(
                 web::resource("/graphql")
+                    .wrap(query_limits)
+                    .wrap(auth.clone())
                     .route(web::post().to(graphql_route))
                     .route(web::get().to(graphql_route)),
             )
-            .service(web::resource("/playground").route(web::get().to(playground_route)))
-            .service(web::resource("/graphiql").route(web::get().to(graphiql_route)))
+            .service(
+                web::resource("/subscriptions")
+                    .app_data(web::Data::new(graphql_limits))
+                    .wrap(auth.clone())
+                    .route(web::get().to(subscriptions_route)),
+            )
+            .service(
+                web::resource("/photo/{hash}")
+                    .wrap(auth.clone())
+                    .route(web::get().to(photo_route)),
+            )
+            .service(
+                web::resource("/calendar.ics")
+                    .wrap(auth.clone().allow_query_token())
+                    .route(web::get().to(calendar_route)),
+            )
+            .service(web::resource("/health").route(web::get().to(health_route)))
+            .service(web::resource("/ready").route(web::get().to(ready_route)))
+            .configure(|cfg| {
+                if enable_playground {
+                    cfg.service(
+                        web::resource("/playground").route(web::get().to(playground_route)),
+                    )
+                    .service(web::resource("/graphiql").route(web::get().to(graphiql_route)));
+                }
+            })
     })
+    .disable_signals()
     .bind("0.0.0.0:8080")
     .unwrap()
-    .run()
-    .await
-    .unwrap();
+    .run();
+
+    let handle = server.handle();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+
+        tracing::info!("gracefully stopping server");
+        shutdown.cancel();
+        handle.stop(true).await;
+    });
+
+    server.await.unwrap();
+
+    if let Some(sync_task) = sync_task {
+        tracing::info!("waiting for background sync to stop");
+        let _ = sync_task.await;
+    }
+
+    tracing::info!("server stopped");
+}
+
+/// Periodically sync with Paprika until shutdown, skipping runs while another
+/// sync is in progress. Errors are logged and the next run is attempted as
+/// scheduled.
+async fn background_sync(conns: Connections, period: Duration) {
+    tracing::info!("syncing every {} seconds", period.as_secs());
+
+    let mut interval = tokio::time::interval(period);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => (),
+            _ = conns.shutdown.cancelled() => break,
+        }
+
+        let _guard = match conns.sync_lock.try_lock() {
+            Ok(guard) => guard,
+            Err(_err) => {
+                tracing::info!("sync already in progress, skipping scheduled sync");
+                continue;
+            }
+        };
+
+        tracing::info!("starting scheduled sync");
+        match updates::check_for_updates(
+            &conns.paprika,
+            &conns.pool,
+            &conns.sync_events,
+            conns.webhook.as_ref(),
+            &conns.shutdown,
+        )
+        .await
+        {
+            Ok(changes) => tracing::info!("scheduled sync completed: {:?}", changes),
+            Err(_err) if conns.shutdown.is_cancelled() => {
+                tracing::info!("scheduled sync cancelled by shutdown")
+            }
+            Err(err) => tracing::error!("scheduled sync failed: {:?}", err),
+        }
+    }
+}
+
+/// Wait for a signal indicating the process should shut down.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("could not install SIGTERM handler");
+
+        tokio::select! {
+            _ = sigterm.recv() => tracing::info!("got SIGTERM"),
+            _ = tokio::signal::ctrl_c() => tracing::info!("got SIGINT"),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("could not install ctrl-c handler");
+        tracing::info!("got ctrl-c");
+    }
+}
+
+/// Machine-readable category for a failed operation, exposed to clients as
+/// the `code` extension on GraphQL errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ErrorCode {
+    Connection,
+    Serialization,
+    NotFound,
+    Database,
+    InvalidArgument,
+    Internal,
+}
+
+impl ErrorCode {
+    fn from_sqlx(err: &sqlx::Error) -> Self {
+        match err {
+            sqlx::Error::RowNotFound => Self::NotFound,
+            sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed => Self::Connection,
+            sqlx::Error::ColumnDecode { .. }
+            | sqlx::Error::Decode(_)
+            | sqlx::Error::ColumnNotFound(_)
+            | sqlx::Error::ColumnIndexOutOfBounds { .. }
+            | sqlx::Error::TypeNotFound { .. } => Self::Serialization,
+            sqlx::Error::Database(_) => Self::Database,
+            _ => Self::Internal,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Connection => "CONNECTION",
+            Self::Serialization => "SERIALIZATION",
+            Self::NotFound => "NOT_FOUND",
+            Self::Database => "DATABASE",
+            Self::InvalidArgument => "INVALID_ARGUMENT",
+            Self::Internal => "INTERNAL",
+        }
+    }
+
+    /// Create a field error with a generic message and this code attached.
+    fn field_error(self, context: &Context, message: &str) -> FieldError {
+        FieldError::new(message, context.error_extensions(Some(self.as_str())))
+    }
+}
+
+/// Log the full database error and convert it into a field error that only
+/// exposes its category to clients.
+fn db_error(context: &Context, message: &str, err: sqlx::Error) -> FieldError {
+    tracing::error!("{}: {:?}", message, err);
+    ErrorCode::from_sqlx(&err).field_error(context, message)
 }
 
 #[derive(Clone, Copy, Debug)]
-struct DbError;
+struct DbError(ErrorCode);
+
+impl DbError {
+    fn from_sqlx(err: sqlx::Error) -> Self {
+        tracing::error!("could not load batch: {:?}", err);
+        Self(ErrorCode::from_sqlx(&err))
+    }
+
+    fn field_error(self, context: &Context, message: &str) -> FieldError {
+        self.0.field_error(context, message)
+    }
+}
+
+/// Build the results for a loader batch.
+///
+/// Every requested key must have a result, otherwise the loader panics, so
+/// keys without a matching row resolve to a not found error.
+fn batch_results<V, F>(
+    keys: &[String],
+    rows: Result<Vec<V>, sqlx::Error>,
+    uid: F,
+) -> std::collections::HashMap<String, Result<V, DbError>>
+where
+    F: Fn(&V) -> &str,
+{
+    let rows = match rows {
+        Ok(rows) => rows,
+        Err(err) => {
+            let err = DbError::from_sqlx(err);
+            return keys.iter().map(|k| (k.to_owned(), Err(err))).collect();
+        }
+    };
+
+    let mut results: std::collections::HashMap<_, _> = keys
+        .iter()
+        .map(|k| (k.to_owned(), Err(DbError(ErrorCode::NotFound))))
+        .collect();
+    results.extend(rows.into_iter().map(|row| (uid(&row).to_owned(), Ok(row))));
+
+    results
+}
 
 #[derive(Clone)]
 struct Connections {
     pool: sqlx::Pool<sqlx::Postgres>,
     paprika: Arc<PaprikaClient>,
+    sync_lock: Arc<tokio::sync::Mutex<()>>,
+    sync_events: tokio::sync::broadcast::Sender<updates::CollectionChanges>,
+    webhook: Option<webhook::Webhook>,
+    shutdown: tokio_util::sync::CancellationToken,
+    photo_dir: PathBuf,
 }
 
+/// Number of sync events retained for subscribers that have fallen behind.
+const SYNC_EVENTS_CAPACITY: usize = 64;
+
+/// State for executing GraphQL operations.
+///
+/// A new context is created for every request to `/graphql`, so the loaders
+/// only cache items while that request, or the operations in a batch, run.
+/// Changes made by a sync are visible to the next request without any
+/// expiration. Subscriptions use one context for their whole connection, so
+/// their fields shouldn't use the loaders for data that changes with a sync.
 #[derive(Clone)]
 struct Context {
     conns: Arc<Connections>,
+    /// Id of the request the context was created for, included in errors.
+    request_id: Option<String>,
 
     recipe_loader: Loader<String, Result<Recipe, DbError>, RecipeBatcher>,
     aisle_loader: Loader<String, Result<Aisle, DbError>, AisleBatcher>,
@@ -90,9 +449,48 @@ struct Context {
     category_loader: Loader<String, Result<Category, DbError>, CategoryBatcher>,
 }
 
+impl Context {
+    fn new(conns: Arc<Connections>, request_id: Option<String>) -> Self {
+        Self {
+            request_id,
+            recipe_loader: Loader::new(RecipeBatcher(conns.pool.clone())),
+            aisle_loader: Loader::new(AisleBatcher(conns.pool.clone())),
+            meal_type_loader: Loader::new(MealTypeBatcher(conns.pool.clone())),
+            grocery_list_loader: Loader::new(GroceryListBatcher(conns.pool.clone())),
+            menu_loader: Loader::new(MenuBatcher(conns.pool.clone())),
+            category_loader: Loader::new(CategoryBatcher(conns.pool.clone())),
+
+            conns,
+        }
+    }
+}
+
+impl Context {
+    /// Extensions for a field error, with its code if it has one and the id
+    /// of the request so errors reported by clients can be matched with logs.
+    fn error_extensions(&self, code: Option<&str>) -> juniper::Value {
+        let mut extensions = juniper::Object::with_capacity(2);
+        if let Some(code) = code {
+            extensions.add_field("code", juniper::Value::scalar(code.to_string()));
+        }
+        if let Some(request_id) = &self.request_id {
+            extensions.add_field("requestId", juniper::Value::scalar(request_id.clone()));
+        }
+
+        if extensions.field_count() == 0 {
+            juniper::Value::null()
+        } else {
+            juniper::Value::Object(extensions)
+        }
+    }
+}
+
 impl juniper::Context for Context {}
 
-#[derive(Debug, Clone)]
+const DEFAULT_SEARCH_LIMIT: i32 = 25;
+const MAX_SEARCH_LIMIT: i32 = 100;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
 struct Recipe {
     id: i32,
     uid: String,
@@ -109,11 +507,45 @@ struct Recipe {
     notes: String,
 
     categories: Vec<String>,
+
+    rating: i32,
+    on_favorites: bool,
+    is_pinned: bool,
+    in_trash: bool,
+
+    source: Option<String>,
+    source_url: Option<String>,
+    servings: Option<String>,
+    difficulty: Option<String>,
+    scale: Option<String>,
+    image_url: Option<String>,
+    nutritional_info: Option<String>,
+    cook_time_minutes: Option<i32>,
+    prep_time_minutes: Option<i32>,
+    total_time_minutes: Option<i32>,
+    created: chrono::DateTime<chrono::Utc>,
 }
 
 impl Recipe {
-    async fn all(context: &Context) -> Result<Vec<Recipe>, FieldError> {
-        let recipes = sqlx::query_as!(
+    async fn all(
+        context: &Context,
+        filter: Option<RecipeFilter>,
+        order: Option<RecipeOrder>,
+    ) -> Result<Vec<Recipe>, FieldError> {
+        RecipeQuery {
+            filter: filter.unwrap_or_default(),
+            order,
+            ..Default::default()
+        }
+        .fetch(context)
+        .await
+    }
+
+    /// Recipes not in the trash with a total time of at most the given
+    /// number of minutes, quickest first. Recipes without a total time are
+    /// excluded.
+    async fn quick(context: &Context, max_minutes: i32) -> Result<Vec<Recipe>, FieldError> {
+        sqlx::query_as!(
             Recipe,
             r#"SELECT
                 id,
@@ -126,18 +558,80 @@ impl Recipe {
                 directions,
                 ingredients,
                 notes,
-                array(select category_uid from recipe_category where recipe_category.recipe_uid = recipe.uid) "categories!"
+                array(select category_uid from recipe_category where recipe_category.recipe_uid = recipe.uid) "categories!",
+                rating,
+                on_favorites,
+                is_pinned,
+                in_trash,
+                source,
+                source_url,
+                servings,
+                difficulty,
+                scale,
+                image_url,
+                nutritional_info,
+                cook_time_minutes,
+                prep_time_minutes,
+                total_time_minutes,
+                created
             FROM
-                recipe"#
+                recipe
+            WHERE
+                NOT in_trash
+                AND total_time_minutes <= $1
+            ORDER BY
+                total_time_minutes, id"#,
+            max_minutes
         )
         .fetch_all(&context.conns.pool)
         .await
-        .map_err(|err| {
-            tracing::error!("recipe fetch error: {:?}", err);
-            FieldError::new("could not query database", graphql_value!(None))
-        })?;
+        .map_err(|err| db_error(context, "could not query database", err))
+    }
 
-        Ok(recipes)
+    /// A random recipe not in the trash, optionally only from favorites.
+    async fn random(context: &Context, only_favorites: bool) -> Result<Option<Recipe>, FieldError> {
+        sqlx::query_as!(
+            Recipe,
+            r#"SELECT
+                id,
+                uid,
+                name,
+                cook_time,
+                prep_time,
+                total_time,
+                description,
+                directions,
+                ingredients,
+                notes,
+                array(select category_uid from recipe_category where recipe_category.recipe_uid = recipe.uid) "categories!",
+                rating,
+                on_favorites,
+                is_pinned,
+                in_trash,
+                source,
+                source_url,
+                servings,
+                difficulty,
+                scale,
+                image_url,
+                nutritional_info,
+                cook_time_minutes,
+                prep_time_minutes,
+                total_time_minutes,
+                created
+            FROM
+                recipe
+            WHERE
+                NOT in_trash
+                AND (on_favorites OR NOT $1)
+            ORDER BY
+                random()
+            LIMIT 1"#,
+            only_favorites
+        )
+        .fetch_optional(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not query database", err))
     }
 
     async fn from_id(context: &Context, id: i32) -> Result<Option<Recipe>, FieldError> {
@@ -154,7 +648,22 @@ impl Recipe {
                 directions,
                 ingredients,
                 notes,
-                array(select category_uid from recipe_category where recipe_category.recipe_uid = recipe.uid) "categories!"
+                array(select category_uid from recipe_category where recipe_category.recipe_uid = recipe.uid) "categories!",
+                rating,
+                on_favorites,
+                is_pinned,
+                in_trash,
+                source,
+                source_url,
+                servings,
+                difficulty,
+                scale,
+                image_url,
+                nutritional_info,
+                cook_time_minutes,
+                prep_time_minutes,
+                total_time_minutes,
+                created
             FROM
                 recipe
             WHERE
@@ -163,7 +672,227 @@ impl Recipe {
         )
         .fetch_optional(&context.conns.pool)
         .await
-        .map_err(|_err| FieldError::new("could not query database", graphql_value!(None)))
+        .map_err(|err| db_error(context, "could not query database", err))
+    }
+
+    /// Apply a change to the current version of a recipe on Paprika and upload
+    /// it, returning the updated recipe or `None` if no recipe has the ID.
+    async fn update_paprika<F>(
+        context: &Context,
+        id: i32,
+        change: F,
+    ) -> Result<Option<paprika_client::PaprikaRecipe>, FieldError>
+    where
+        F: FnOnce(&mut paprika_client::PaprikaRecipe),
+    {
+        let uid = sqlx::query_scalar!("SELECT uid FROM recipe WHERE id = $1", id)
+            .fetch_optional(&context.conns.pool)
+            .await
+            .map_err(|err| db_error(context, "could not query database", err))?;
+
+        let uid = match uid {
+            Some(uid) => uid,
+            None => return Ok(None),
+        };
+
+        let mut recipe = context.conns.paprika.recipe(&uid).await?;
+        change(&mut recipe);
+
+        let recipe = context.conns.paprika.update_recipe(recipe).await?;
+
+        Ok(Some(recipe))
+    }
+
+    async fn from_uid(context: &Context, uid: &str) -> Result<Option<Recipe>, FieldError> {
+        sqlx::query_as!(
+            Recipe,
+            r#"SELECT
+                id,
+                uid,
+                name,
+                cook_time,
+                prep_time,
+                total_time,
+                description,
+                directions,
+                ingredients,
+                notes,
+                array(select category_uid from recipe_category where recipe_category.recipe_uid = recipe.uid) "categories!",
+                rating,
+                on_favorites,
+                is_pinned,
+                in_trash,
+                source,
+                source_url,
+                servings,
+                difficulty,
+                scale,
+                image_url,
+                nutritional_info,
+                cook_time_minutes,
+                prep_time_minutes,
+                total_time_minutes,
+                created
+            FROM
+                recipe
+            WHERE
+                uid = $1"#,
+            uid
+        )
+        .fetch_optional(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not query database", err))
+    }
+
+    async fn from_name(context: &Context, name: &str) -> Result<Option<Recipe>, FieldError> {
+        sqlx::query_as!(
+            Recipe,
+            r#"SELECT
+                id,
+                uid,
+                name,
+                cook_time,
+                prep_time,
+                total_time,
+                description,
+                directions,
+                ingredients,
+                notes,
+                array(select category_uid from recipe_category where recipe_category.recipe_uid = recipe.uid) "categories!",
+                rating,
+                on_favorites,
+                is_pinned,
+                in_trash,
+                source,
+                source_url,
+                servings,
+                difficulty,
+                scale,
+                image_url,
+                nutritional_info,
+                cook_time_minutes,
+                prep_time_minutes,
+                total_time_minutes,
+                created
+            FROM
+                recipe
+            WHERE
+                name = $1
+                AND NOT in_trash
+            ORDER BY
+                id
+            LIMIT 1"#,
+            name
+        )
+        .fetch_optional(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not query database", err))
+    }
+
+    async fn search(
+        context: &Context,
+        query: &str,
+        limit: Option<i32>,
+    ) -> Result<Vec<Self>, FieldError> {
+        let limit = limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .clamp(1, MAX_SEARCH_LIMIT);
+
+        sqlx::query_as!(
+            Self,
+            r#"SELECT
+                id,
+                uid,
+                name,
+                cook_time,
+                prep_time,
+                total_time,
+                description,
+                directions,
+                ingredients,
+                notes,
+                array(select category_uid from recipe_category where recipe_category.recipe_uid = recipe.uid) "categories!",
+                rating,
+                on_favorites,
+                is_pinned,
+                in_trash,
+                source,
+                source_url,
+                servings,
+                difficulty,
+                scale,
+                image_url,
+                nutritional_info,
+                cook_time_minutes,
+                prep_time_minutes,
+                total_time_minutes,
+                created
+            FROM
+                recipe,
+                plainto_tsquery('english', $1) query
+            WHERE
+                search @@ query
+                AND NOT in_trash
+            ORDER BY
+                ts_rank(search, query) DESC
+            LIMIT $2"#,
+            query,
+            i64::from(limit)
+        )
+        .fetch_all(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not query database", err))
+    }
+
+    /// Find recipes with an ingredient containing the term, ignoring case.
+    async fn with_ingredient(context: &Context, term: &str) -> Result<Vec<Self>, FieldError> {
+        let pattern = format!(
+            "%{}%",
+            term.replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+
+        sqlx::query_as!(
+            Self,
+            r#"SELECT
+                id,
+                uid,
+                name,
+                cook_time,
+                prep_time,
+                total_time,
+                description,
+                directions,
+                ingredients,
+                notes,
+                array(select category_uid from recipe_category where recipe_category.recipe_uid = recipe.uid) "categories!",
+                rating,
+                on_favorites,
+                is_pinned,
+                in_trash,
+                source,
+                source_url,
+                servings,
+                difficulty,
+                scale,
+                image_url,
+                nutritional_info,
+                cook_time_minutes,
+                prep_time_minutes,
+                total_time_minutes,
+                created
+            FROM
+                recipe
+            WHERE
+                ingredients ILIKE $1
+                AND NOT in_trash
+            ORDER BY name"#,
+            pattern
+        )
+        .fetch_all(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not query database", err))
     }
 
     async fn in_category(context: &Context, category_uid: &str) -> Result<Vec<Self>, FieldError> {
@@ -180,18 +909,93 @@ impl Recipe {
                 directions,
                 ingredients,
                 notes,
-                array(select category_uid from recipe_category where recipe_category.recipe_uid = recipe.uid) "categories!"
+                array(select category_uid from recipe_category where recipe_category.recipe_uid = recipe.uid) "categories!",
+                rating,
+                on_favorites,
+                is_pinned,
+                in_trash,
+                source,
+                source_url,
+                servings,
+                difficulty,
+                scale,
+                image_url,
+                nutritional_info,
+                cook_time_minutes,
+                prep_time_minutes,
+                total_time_minutes,
+                created
             FROM
                 recipe
             JOIN recipe_category
                 ON recipe.uid = recipe_category.recipe_uid
             WHERE
-                recipe_category.category_uid = $1"#,
+                recipe_category.category_uid = $1
+                AND NOT in_trash"#,
             category_uid
         )
         .fetch_all(&context.conns.pool)
         .await
-        .map_err(|_err| FieldError::new("could not query database", graphql_value!(None)))
+        .map_err(|err| db_error(context, "could not query database", err))
+    }
+
+    /// Recipes whose stored hash differs from the one currently on Paprika,
+    /// or that no longer exist on Paprika, meaning the database is stale.
+    ///
+    /// Only the list of recipe hashes is fetched, not the recipes themselves.
+    async fn needing_resync(context: &Context) -> Result<Vec<Self>, FieldError> {
+        let (uids, hashes): (Vec<_>, Vec<_>) = context
+            .conns
+            .paprika
+            .recipes()
+            .await?
+            .into_iter()
+            .map(|recipe| (recipe.uid, recipe.hash))
+            .unzip();
+
+        sqlx::query_as!(
+            Self,
+            r#"SELECT
+                id,
+                uid,
+                name,
+                cook_time,
+                prep_time,
+                total_time,
+                description,
+                directions,
+                ingredients,
+                notes,
+                array(select category_uid from recipe_category where recipe_category.recipe_uid = recipe.uid) "categories!",
+                rating,
+                on_favorites,
+                is_pinned,
+                in_trash,
+                source,
+                source_url,
+                servings,
+                difficulty,
+                scale,
+                image_url,
+                nutritional_info,
+                cook_time_minutes,
+                prep_time_minutes,
+                total_time_minutes,
+                created
+            FROM
+                recipe
+            LEFT JOIN unnest($1::text[], $2::text[]) current_recipe(current_uid, current_hash)
+                ON recipe.uid = current_recipe.current_uid
+            WHERE
+                current_recipe.current_hash IS DISTINCT FROM recipe.hash
+            ORDER BY
+                name"#,
+            &uids,
+            &hashes
+        )
+        .fetch_all(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not query database", err))
     }
 }
 
@@ -217,6 +1021,18 @@ impl Recipe {
         self.total_time.as_deref().filter(|s| !s.trim().is_empty())
     }
 
+    fn cook_time_minutes(&self) -> Option<i32> {
+        self.cook_time_minutes
+    }
+
+    fn prep_time_minutes(&self) -> Option<i32> {
+        self.prep_time_minutes
+    }
+
+    fn total_time_minutes(&self) -> Option<i32> {
+        self.total_time_minutes
+    }
+
     fn description(&self) -> Option<&str> {
         self.description.as_deref().filter(|s| !s.trim().is_empty())
     }
@@ -229,6 +1045,28 @@ impl Recipe {
         &self.ingredients
     }
 
+    /// Ingredient lines with their quantities multiplied by a factor, which
+    /// must be a finite number greater than zero.
+    fn scaled_ingredients(
+        &self,
+        context: &Context,
+        factor: f64,
+    ) -> Result<Vec<String>, FieldError> {
+        if !factor.is_finite() || factor <= 0.0 {
+            return Err(ErrorCode::InvalidArgument
+                .field_error(context, "factor must be a finite number greater than zero"));
+        }
+
+        Ok(paprika_client::scale_ingredients(&self.ingredients, factor))
+    }
+
+    fn parsed_ingredients(&self) -> Vec<ParsedIngredient> {
+        paprika_client::parse_ingredients(&self.ingredients)
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+
     fn notes(&self) -> Option<&str> {
         if self.notes.trim().is_empty() {
             None
@@ -237,20 +1075,93 @@ impl Recipe {
         }
     }
 
-    async fn meals(&self, context: &Context) -> Result<Vec<Meal>, FieldError> {
-        Meal::by_recipe_uid(context, &self.uid).await
+    fn rating(&self) -> i32 {
+        self.rating
     }
 
-    async fn categories(&self, context: &Context) -> Result<Vec<Category>, FieldError> {
-        context
+    fn on_favorites(&self) -> bool {
+        self.on_favorites
+    }
+
+    fn is_pinned(&self) -> bool {
+        self.is_pinned
+    }
+
+    fn in_trash(&self) -> bool {
+        self.in_trash
+    }
+
+    fn source(&self) -> Option<&str> {
+        self.source.as_deref().filter(|s| !s.trim().is_empty())
+    }
+
+    fn source_url(&self) -> Option<&str> {
+        self.source_url.as_deref().filter(|s| !s.trim().is_empty())
+    }
+
+    /// The domain of `sourceUrl`, without any `www.` prefix.
+    fn source_domain(&self) -> Option<String> {
+        self.source_url
+            .as_deref()
+            .and_then(paprika_client::url_domain)
+    }
+
+    fn servings(&self) -> Option<&str> {
+        self.servings.as_deref().filter(|s| !s.trim().is_empty())
+    }
+
+    /// The number of servings, such as 4 for "Serves 4" or "4-6", if the
+    /// servings contain a number.
+    fn servings_count(&self) -> Option<f64> {
+        self.servings
+            .as_deref()
+            .and_then(paprika_client::parse_servings)
+    }
+
+    fn difficulty(&self) -> Option<&str> {
+        self.difficulty.as_deref().filter(|s| !s.trim().is_empty())
+    }
+
+    /// The difficulty standardized from the text in `difficulty`.
+    fn difficulty_level(&self) -> Option<RecipeDifficulty> {
+        self.difficulty
+            .as_deref()
+            .filter(|difficulty| !difficulty.trim().is_empty())
+            .and_then(|difficulty| difficulty.parse().ok())
+            .map(|difficulty: paprika_client::Difficulty| (&difficulty).into())
+    }
+
+    fn scale(&self) -> Option<&str> {
+        self.scale.as_deref().filter(|s| !s.trim().is_empty())
+    }
+
+    fn image_url(&self) -> Option<&str> {
+        self.image_url.as_deref().filter(|s| !s.trim().is_empty())
+    }
+
+    /// Nutrition details as free text, exactly as entered in Paprika.
+    fn nutritional_info(&self) -> Option<&str> {
+        self.nutritional_info
+            .as_deref()
+            .filter(|s| !s.trim().is_empty())
+    }
+
+    fn created(&self) -> chrono::DateTime<chrono::Utc> {
+        self.created
+    }
+
+    async fn meals(&self, context: &Context) -> Result<Vec<Meal>, FieldError> {
+        Meal::by_recipe_uid(context, &self.uid).await
+    }
+
+    async fn categories(&self, context: &Context) -> Result<Vec<Category>, FieldError> {
+        context
             .category_loader
             .load_many(self.categories.clone())
             .await
-            .into_iter()
-            .map(|(_uid, category)| {
-                category.map_err(|_err| {
-                    FieldError::new("item should always have category", graphql_value!(None))
-                })
+            .into_values()
+            .map(|category| {
+                category.map_err(|err| err.field_error(context, "item should always have category"))
             })
             .collect()
     }
@@ -260,6 +1171,375 @@ impl Recipe {
     }
 }
 
+/// A recipe's difficulty. Difficulties that aren't easy, medium, or hard are
+/// other difficulties.
+#[derive(juniper::GraphQLEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum RecipeDifficulty {
+    Easy,
+    Medium,
+    Hard,
+    Other,
+}
+
+impl From<&paprika_client::Difficulty> for RecipeDifficulty {
+    fn from(difficulty: &paprika_client::Difficulty) -> Self {
+        match difficulty {
+            paprika_client::Difficulty::Easy => Self::Easy,
+            paprika_client::Difficulty::Medium => Self::Medium,
+            paprika_client::Difficulty::Hard => Self::Hard,
+            paprika_client::Difficulty::Other(_) => Self::Other,
+        }
+    }
+}
+
+/// How to order listed recipes.
+///
+/// Results are always ordered by ID after any requested ordering, so pages
+/// after a given recipe ID remain stable.
+#[derive(juniper::GraphQLEnum, Clone, Copy, Debug)]
+enum RecipeOrder {
+    NameAsc,
+    NameDesc,
+    RatingDesc,
+    CreatedDesc,
+}
+
+impl RecipeOrder {
+    /// The value of the column sorted by for a recipe, as stored in cursors.
+    fn cursor_key(self, recipe: &Recipe) -> String {
+        match self {
+            Self::NameAsc | Self::NameDesc => recipe.name.clone(),
+            Self::RatingDesc => recipe.rating.to_string(),
+            Self::CreatedDesc => recipe.created.to_rfc3339(),
+        }
+    }
+
+    /// Bind a sort key from a cursor as the type of the column sorted by.
+    fn push_cursor_key(
+        self,
+        context: &Context,
+        query: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>,
+        key: &str,
+    ) -> Result<(), FieldError> {
+        match self {
+            Self::NameAsc | Self::NameDesc => {
+                query.push_bind(key.to_string());
+            }
+            Self::RatingDesc => {
+                query.push_bind(key.parse::<i32>().map_err(|_err| invalid_cursor(context))?);
+            }
+            Self::CreatedDesc => {
+                let created = chrono::DateTime::parse_from_rfc3339(key)
+                    .map_err(|_err| invalid_cursor(context))?
+                    .with_timezone(&chrono::Utc);
+                query.push_bind(created);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The column to sort by and if the sort is descending.
+    fn sort(self) -> (&'static str, bool) {
+        match self {
+            Self::NameAsc => ("name", false),
+            Self::NameDesc => ("name", true),
+            Self::RatingDesc => ("rating", true),
+            Self::CreatedDesc => ("created", true),
+        }
+    }
+}
+
+/// Conditions recipes must all match to be listed. Recipes in the trash are
+/// excluded unless `includeTrashed` is set.
+#[derive(juniper::GraphQLInputObject, Default)]
+struct RecipeFilter {
+    min_rating: Option<i32>,
+    only_favorites: Option<bool>,
+    only_pinned: Option<bool>,
+    /// If recipes in the trash should be included, defaults to false.
+    include_trashed: Option<bool>,
+    /// Only include recipes with a cook time of at most this many minutes.
+    max_cook_time_minutes: Option<i32>,
+    difficulty: Option<RecipeDifficulty>,
+}
+
+#[derive(Default)]
+struct RecipeQuery {
+    filter: RecipeFilter,
+    order: Option<RecipeOrder>,
+    after: Option<Cursor>,
+    limit: Option<i64>,
+}
+
+impl RecipeQuery {
+    async fn fetch(&self, context: &Context) -> Result<Vec<Recipe>, FieldError> {
+        // Only static column names are ever added to the query, everything
+        // provided by users must be bound.
+        let (column, descending) = self.order.map(RecipeOrder::sort).unwrap_or(("id", false));
+        let (direction, comparison) = if descending {
+            ("DESC", "<")
+        } else {
+            ("ASC", ">")
+        };
+
+        let mut query = sqlx::QueryBuilder::<sqlx::Postgres>::new(
+            r#"SELECT
+                id,
+                uid,
+                name,
+                cook_time,
+                prep_time,
+                total_time,
+                description,
+                directions,
+                ingredients,
+                notes,
+                array(select category_uid from recipe_category where recipe_category.recipe_uid = recipe.uid) categories,
+                rating,
+                on_favorites,
+                is_pinned,
+                in_trash,
+                source,
+                source_url,
+                servings,
+                difficulty,
+                scale,
+                image_url,
+                nutritional_info,
+                cook_time_minutes,
+                prep_time_minutes,
+                total_time_minutes,
+                created
+            FROM
+                recipe
+            WHERE
+                true"#,
+        );
+
+        if !self.filter.include_trashed.unwrap_or(false) {
+            query.push(" AND NOT in_trash");
+        }
+
+        if let Some(min_rating) = self.filter.min_rating {
+            query.push(" AND rating >= ").push_bind(min_rating);
+        }
+
+        if self.filter.only_favorites.unwrap_or(false) {
+            query.push(" AND on_favorites");
+        }
+
+        if self.filter.only_pinned.unwrap_or(false) {
+            query.push(" AND is_pinned");
+        }
+
+        if let Some(difficulty) = self.filter.difficulty {
+            match difficulty {
+                RecipeDifficulty::Easy => query.push(" AND lower(trim(difficulty)) = 'easy'"),
+                RecipeDifficulty::Medium => query.push(" AND lower(trim(difficulty)) = 'medium'"),
+                RecipeDifficulty::Hard => query.push(" AND lower(trim(difficulty)) = 'hard'"),
+                RecipeDifficulty::Other => query.push(
+                    " AND trim(difficulty) <> '' AND lower(trim(difficulty)) NOT IN ('easy', 'medium', 'hard')",
+                ),
+            };
+        }
+
+        if let Some(max_cook_time_minutes) = self.filter.max_cook_time_minutes {
+            query
+                .push(" AND cook_time_minutes <= ")
+                .push_bind(max_cook_time_minutes);
+        }
+
+        // Cursors contain the sort key of their recipe, so pages continue in
+        // the right place even if that recipe has since been deleted.
+        if let Some(after) = &self.after {
+            match (self.order, after.key.as_deref()) {
+                (None, None) => {
+                    query.push(" AND id > ").push_bind(after.id);
+                }
+                (Some(order), Some(key)) => {
+                    query.push(format!(
+                        " AND ({column}, id) {comparison} (",
+                        column = column,
+                        comparison = comparison
+                    ));
+                    order.push_cursor_key(context, &mut query, key)?;
+                    query.push(", ").push_bind(after.id).push(")");
+                }
+                // The cursor was created with a different order.
+                _ => return Err(invalid_cursor(context)),
+            }
+        }
+
+        query.push(format!(
+            " ORDER BY {column} {direction}, id {direction}",
+            column = column,
+            direction = direction
+        ));
+
+        if let Some(limit) = self.limit {
+            query.push(" LIMIT ").push_bind(limit);
+        }
+
+        query
+            .build_query_as::<Recipe>()
+            .fetch_all(&context.conns.pool)
+            .await
+            .map_err(|err| db_error(context, "could not query database", err))
+    }
+}
+
+const DEFAULT_PAGE_SIZE: i32 = 25;
+const MAX_PAGE_SIZE: i32 = 100;
+
+/// A single line from a recipe's ingredients, split into its parts.
+#[derive(GraphQLObject, Debug, Clone)]
+struct ParsedIngredient {
+    /// The original line.
+    raw: String,
+    /// The leading quantity, using the lower bound of ranges.
+    quantity: Option<f64>,
+    /// A recognized unit, in its canonical abbreviated form.
+    unit: Option<String>,
+    /// The remainder of the line, or the header text for section headers.
+    name: String,
+    /// If this line is a section header instead of an ingredient.
+    is_header: bool,
+}
+
+impl From<paprika_client::ParsedIngredient> for ParsedIngredient {
+    fn from(ingredient: paprika_client::ParsedIngredient) -> Self {
+        Self {
+            raw: ingredient.raw,
+            quantity: ingredient.quantity,
+            unit: ingredient.unit,
+            name: ingredient.name,
+            is_header: ingredient.is_header,
+        }
+    }
+}
+
+#[derive(GraphQLObject)]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(GraphQLObject)]
+#[graphql(context = Context)]
+struct RecipeEdge {
+    cursor: String,
+    node: Recipe,
+}
+
+/// A page of recipes. Passing `pageInfo.endCursor` as `after` loads the next
+/// page.
+#[derive(GraphQLObject)]
+#[graphql(context = Context)]
+struct RecipeConnection {
+    edges: Vec<RecipeEdge>,
+    page_info: PageInfo,
+}
+
+impl RecipeConnection {
+    async fn load(
+        context: &Context,
+        first: Option<i32>,
+        after: Option<String>,
+        filter: Option<RecipeFilter>,
+        order: Option<RecipeOrder>,
+    ) -> Result<Self, FieldError> {
+        let first = first.unwrap_or(DEFAULT_PAGE_SIZE).clamp(0, MAX_PAGE_SIZE);
+        let after = after
+            .as_deref()
+            .map(|after| Cursor::decode(context, after))
+            .transpose()?;
+
+        // Request one more item than needed to determine if there is another
+        // page without an additional query.
+        let mut recipes = RecipeQuery {
+            filter: filter.unwrap_or_default(),
+            order,
+            after,
+            limit: Some(i64::from(first) + 1),
+        }
+        .fetch(context)
+        .await?;
+        let has_next_page = recipes.len() > first as usize;
+        recipes.truncate(first as usize);
+
+        let edges: Vec<_> = recipes
+            .into_iter()
+            .map(|recipe| RecipeEdge {
+                cursor: Cursor {
+                    id: recipe.id,
+                    key: order.map(|order| order.cursor_key(&recipe)),
+                }
+                .encode(),
+                node: recipe,
+            })
+            .collect();
+
+        let end_cursor = edges.last().map(|edge| edge.cursor.clone());
+
+        Ok(Self {
+            edges,
+            page_info: PageInfo {
+                has_next_page,
+                end_cursor,
+            },
+        })
+    }
+}
+
+/// Position in a list of recipes, given to clients as an opaque string.
+#[derive(Clone, Debug, PartialEq)]
+struct Cursor {
+    /// ID of the last recipe on the page.
+    id: i32,
+    /// Value of the column the list is ordered by for the last recipe, if
+    /// it's ordered by anything other than ID.
+    key: Option<String>,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        let cursor = match &self.key {
+            Some(key) => format!("cursor:{}:{}", self.id, key),
+            None => format!("cursor:{}", self.id),
+        };
+
+        base64::engine::general_purpose::STANDARD.encode(cursor)
+    }
+
+    fn decode(context: &Context, cursor: &str) -> Result<Self, FieldError> {
+        base64::engine::general_purpose::STANDARD
+            .decode(cursor)
+            .ok()
+            .and_then(|cursor| String::from_utf8(cursor).ok())
+            .and_then(|cursor| {
+                let cursor = cursor.strip_prefix("cursor:")?;
+                let (id, key) = match cursor.split_once(':') {
+                    Some((id, key)) => (id, Some(key.to_string())),
+                    None => (cursor, None),
+                };
+
+                Some(Self {
+                    id: id.parse().ok()?,
+                    key,
+                })
+            })
+            .ok_or_else(|| invalid_cursor(context))
+    }
+}
+
+fn invalid_cursor(context: &Context) -> FieldError {
+    FieldError::new(
+        "invalid cursor",
+        context.error_extensions(Some("INVALID_CURSOR")),
+    )
+}
+
 struct RecipeBatcher(sqlx::Pool<sqlx::Postgres>);
 
 #[async_trait::async_trait]
@@ -281,7 +1561,22 @@ impl BatchFn<String, Result<Recipe, DbError>> for RecipeBatcher {
                 directions,
                 ingredients,
                 notes,
-                categories
+                categories,
+                rating,
+                on_favorites,
+                is_pinned,
+                in_trash,
+                source,
+                source_url,
+                servings,
+                difficulty,
+                scale,
+                image_url,
+                nutritional_info,
+                cook_time_minutes,
+                prep_time_minutes,
+                total_time_minutes,
+                created
             FROM
                 recipe
             WHERE uid = any($1)",
@@ -290,13 +1585,7 @@ impl BatchFn<String, Result<Recipe, DbError>> for RecipeBatcher {
         .fetch_all(&self.0)
         .await;
 
-        match recipes {
-            Ok(recipes) => recipes
-                .into_iter()
-                .map(|recipe| (recipe.uid.clone(), Ok(recipe)))
-                .collect(),
-            Err(_err) => keys.iter().map(|k| (k.to_owned(), Err(DbError))).collect(),
-        }
+        batch_results(keys, recipes, |recipe| &recipe.uid)
     }
 }
 
@@ -310,14 +1599,34 @@ struct Meal {
 }
 
 impl Meal {
-    async fn all(context: &Context) -> Result<Vec<Self>, FieldError> {
+    /// Meals with a date from the start (inclusive) to the end (exclusive) of
+    /// the range, ordered by date. Either side of the range may be omitted.
+    async fn in_range(
+        context: &Context,
+        from: Option<chrono::DateTime<chrono::Utc>>,
+        to: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<Vec<Self>, FieldError> {
         let meals = sqlx::query_as!(
             Meal,
-            r#"SELECT id, date, name, recipe_uid, type_uid FROM meal"#
+            r#"SELECT
+                id,
+                date,
+                name,
+                recipe_uid,
+                type_uid
+            FROM
+                meal
+            WHERE
+                ($1::timestamptz IS NULL OR date >= $1)
+                AND ($2::timestamptz IS NULL OR date < $2)
+            ORDER BY
+                date"#,
+            from,
+            to
         )
         .fetch_all(&context.conns.pool)
         .await
-        .map_err(|_err| FieldError::new("could not query database", graphql_value!(None)))?;
+        .map_err(|err| db_error(context, "could not query database", err))?;
 
         Ok(meals)
     }
@@ -330,7 +1639,7 @@ impl Meal {
         )
         .fetch_all(&context.conns.pool)
         .await
-        .map_err(|_err| FieldError::new("could not query database", graphql_value!(None)))?;
+        .map_err(|err| db_error(context, "could not query database", err))?;
 
         Ok(meals)
     }
@@ -360,7 +1669,7 @@ impl Meal {
             .recipe_loader
             .load(recipe_uid)
             .await
-            .map_err(|_err| FieldError::new("item should always have recipe", graphql_value!(None)))
+            .map_err(|err| err.field_error(context, "item should always have recipe"))
             .map(Option::Some)
     }
 
@@ -369,9 +1678,7 @@ impl Meal {
             .meal_type_loader
             .load(self.type_uid.clone())
             .await
-            .map_err(|_err| {
-                FieldError::new("item should always have meal type", graphql_value!(None))
-            })
+            .map_err(|err| err.field_error(context, "item should always have meal type"))
     }
 }
 
@@ -387,6 +1694,7 @@ struct GroceryItem {
     aisle_uid: String,
     list_uid: String,
 
+    recipe_uid: Option<String>,
     recipe: Option<String>,
 }
 
@@ -403,13 +1711,14 @@ impl GroceryItem {
                 purchased,
                 aisle_uid,
                 list_uid,
+                recipe_uid,
                 recipe
             FROM
                 grocery_item"#
         )
         .fetch_all(&context.conns.pool)
         .await
-        .map_err(|_err| FieldError::new("could not query database", graphql_value!(None)))
+        .map_err(|err| db_error(context, "could not query database", err))
     }
 
     async fn by_list_uid(context: &Context, list_uid: &str) -> Result<Vec<Self>, FieldError> {
@@ -424,6 +1733,7 @@ impl GroceryItem {
                 purchased,
                 aisle_uid,
                 list_uid,
+                recipe_uid,
                 recipe
             FROM
                 grocery_item
@@ -433,7 +1743,64 @@ impl GroceryItem {
         )
         .fetch_all(&context.conns.pool)
         .await
-        .map_err(|_err| FieldError::new("could not query database", graphql_value!(None)))
+        .map_err(|err| db_error(context, "could not query database", err))
+    }
+
+    async fn from_id(context: &Context, id: i32) -> Result<Option<Self>, FieldError> {
+        sqlx::query_as!(
+            GroceryItem,
+            r#"SELECT
+                id,
+                name,
+                ingredient,
+                quantity,
+                instruction,
+                purchased,
+                aisle_uid,
+                list_uid,
+                recipe_uid,
+                recipe
+            FROM
+                grocery_item
+            WHERE
+                id = $1"#,
+            id
+        )
+        .fetch_optional(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not query database", err))
+    }
+
+    /// Load the complete stored item, as needed to upload changes to Paprika.
+    async fn paprika_item(
+        context: &Context,
+        id: i32,
+    ) -> Result<Option<paprika_client::PaprikaGroceryItem>, FieldError> {
+        sqlx::query_as!(
+            paprika_client::PaprikaGroceryItem,
+            r#"SELECT
+                uid,
+                recipe_uid,
+                name,
+                order_flag,
+                purchased,
+                aisle,
+                ingredient,
+                recipe,
+                instruction,
+                quantity,
+                separate,
+                aisle_uid,
+                list_uid
+            FROM
+                grocery_item
+            WHERE
+                id = $1"#,
+            id
+        )
+        .fetch_optional(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not query database", err))
     }
 }
 
@@ -467,12 +1834,33 @@ impl GroceryItem {
         self.recipe.as_deref()
     }
 
+    /// The recipe this item was added from, if it was not added manually.
+    async fn source_recipe(&self, context: &Context) -> Result<Option<Recipe>, FieldError> {
+        if let Some(recipe_uid) = &self.recipe_uid {
+            return context
+                .recipe_loader
+                .load(recipe_uid.clone())
+                .await
+                .map(Some)
+                .map_err(|err| err.field_error(context, "item should always have recipe"));
+        }
+
+        match self
+            .recipe
+            .as_deref()
+            .filter(|name| !name.trim().is_empty())
+        {
+            Some(name) => Recipe::from_name(context, name).await,
+            None => Ok(None),
+        }
+    }
+
     async fn aisle(&self, context: &Context) -> Result<Aisle, FieldError> {
         context
             .aisle_loader
             .load(self.aisle_uid.clone())
             .await
-            .map_err(|_err| FieldError::new("item should always have aisle", graphql_value!(None)))
+            .map_err(|err| err.field_error(context, "item should always have aisle"))
     }
 
     async fn list(&self, context: &Context) -> Result<GroceryList, FieldError> {
@@ -480,16 +1868,13 @@ impl GroceryItem {
             .grocery_list_loader
             .load(self.list_uid.clone())
             .await
-            .map_err(|_err| {
-                FieldError::new("item should always have grocery list", graphql_value!(None))
-            })
+            .map_err(|err| err.field_error(context, "item should always have grocery list"))
     }
 }
 
 #[derive(GraphQLObject, Debug, Clone)]
 struct Aisle {
     id: i32,
-    #[graphql(skip)]
     uid: String,
     name: String,
     order_flag: i32,
@@ -518,13 +1903,7 @@ impl BatchFn<String, Result<Aisle, DbError>> for AisleBatcher {
         .fetch_all(&self.0)
         .await;
 
-        match aisles {
-            Ok(aisles) => aisles
-                .into_iter()
-                .map(|aisle| (aisle.uid.clone(), Ok(aisle)))
-                .collect(),
-            Err(_err) => keys.iter().map(|k| (k.to_owned(), Err(DbError))).collect(),
-        }
+        batch_results(keys, aisles, |aisle| &aisle.uid)
     }
 }
 
@@ -555,7 +1934,34 @@ impl PantryItem {
         )
         .fetch_all(&context.conns.pool)
         .await
-        .map_err(|_err| FieldError::new("could not query database", graphql_value!(None)))
+        .map_err(|err| db_error(context, "could not query database", err))
+    }
+
+    /// In stock items with an expiration date within the given number of
+    /// days, including items that have already expired, soonest first.
+    async fn expiring_within(context: &Context, days: i32) -> Result<Vec<PantryItem>, FieldError> {
+        sqlx::query_as!(
+            PantryItem,
+            r#"SELECT
+                id,
+                ingredient,
+                expiration_date,
+                in_stock,
+                purchase_date,
+                quantity,
+                aisle_uid
+            FROM
+                pantry_item
+            WHERE
+                has_expiration
+                AND in_stock
+                AND expiration_date <= now() + make_interval(days => $1)
+            ORDER BY expiration_date"#,
+            days.max(0)
+        )
+        .fetch_all(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not query database", err))
     }
 }
 
@@ -590,7 +1996,77 @@ impl PantryItem {
             .aisle_loader
             .load(self.aisle_uid.clone())
             .await
-            .map_err(|_err| FieldError::new("item should always have aisle", graphql_value!(None)))
+            .map_err(|err| err.field_error(context, "item should always have aisle"))
+    }
+}
+
+/// A pantry item that is out of stock, with the grocery ingredient of the
+/// same name if there is one.
+#[derive(GraphQLObject)]
+#[graphql(context = Context)]
+struct RestockSuggestion {
+    pantry_item: PantryItem,
+    grocery_ingredient: Option<GroceryIngredient>,
+}
+
+impl RestockSuggestion {
+    async fn all(context: &Context) -> Result<Vec<Self>, FieldError> {
+        let rows = sqlx::query!(
+            r#"SELECT
+                pantry_item.id,
+                pantry_item.ingredient,
+                pantry_item.expiration_date,
+                pantry_item.in_stock,
+                pantry_item.purchase_date,
+                pantry_item.quantity,
+                pantry_item.aisle_uid,
+                grocery_ingredient.id "grocery_ingredient_id?",
+                grocery_ingredient.name "grocery_ingredient_name?",
+                grocery_ingredient.aisle_uid grocery_ingredient_aisle_uid
+            FROM
+                pantry_item
+            LEFT JOIN LATERAL (
+                SELECT id, name, aisle_uid
+                FROM grocery_ingredient
+                WHERE lower(trim(grocery_ingredient.name)) = lower(trim(pantry_item.ingredient))
+                ORDER BY id
+                LIMIT 1
+            ) grocery_ingredient ON true
+            WHERE
+                NOT pantry_item.in_stock
+            ORDER BY
+                pantry_item.ingredient"#
+        )
+        .fetch_all(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not query database", err))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let aisle_uid = row.grocery_ingredient_aisle_uid;
+
+                Self {
+                    pantry_item: PantryItem {
+                        id: row.id,
+                        ingredient: row.ingredient,
+                        expiration_date: row.expiration_date,
+                        in_stock: row.in_stock,
+                        purchase_date: row.purchase_date,
+                        quantity: row.quantity,
+                        aisle_uid: row.aisle_uid,
+                    },
+                    grocery_ingredient: row
+                        .grocery_ingredient_id
+                        .zip(row.grocery_ingredient_name)
+                        .map(|(id, name)| GroceryIngredient {
+                            id,
+                            name,
+                            aisle_uid,
+                        }),
+                }
+            })
+            .collect())
     }
 }
 
@@ -599,6 +2075,7 @@ struct MealType {
     id: i32,
     uid: String,
     name: String,
+    color: String,
 }
 
 #[graphql_object(context = Context)]
@@ -610,6 +2087,29 @@ impl MealType {
     fn name(&self) -> &str {
         &self.name
     }
+
+    /// The meal type's color, or null if it isn't a valid hex color.
+    fn color(&self) -> Option<Rgb> {
+        paprika_client::parse_hex_color(&self.color).map(Rgb::from)
+    }
+}
+
+/// A color as red, green, and blue components from 0 to 255.
+#[derive(GraphQLObject, Debug, Clone, Copy)]
+struct Rgb {
+    red: i32,
+    green: i32,
+    blue: i32,
+}
+
+impl From<(u8, u8, u8)> for Rgb {
+    fn from((red, green, blue): (u8, u8, u8)) -> Self {
+        Self {
+            red: red.into(),
+            green: green.into(),
+            blue: blue.into(),
+        }
+    }
 }
 
 struct MealTypeBatcher(sqlx::Pool<sqlx::Postgres>);
@@ -622,25 +2122,20 @@ impl BatchFn<String, Result<MealType, DbError>> for MealTypeBatcher {
     ) -> std::collections::HashMap<String, Result<MealType, DbError>> {
         let meal_types = sqlx::query_as!(
             MealType,
-            "SELECT id, uid, name FROM meal_type WHERE uid = any($1)",
+            "SELECT id, uid, name, color FROM meal_type WHERE uid = any($1)",
             keys
         )
         .fetch_all(&self.0)
         .await;
 
-        match meal_types {
-            Ok(meal_types) => meal_types
-                .into_iter()
-                .map(|meal_type| (meal_type.uid.clone(), Ok(meal_type)))
-                .collect(),
-            Err(_err) => keys.iter().map(|k| (k.to_owned(), Err(DbError))).collect(),
-        }
+        batch_results(keys, meal_types, |meal_type| &meal_type.uid)
     }
 }
 
 struct MenuItem {
     id: i32,
     name: String,
+    order_flag: i32,
     recipe_uid: String,
     menu_uid: String,
     type_uid: String,
@@ -651,12 +2146,12 @@ impl MenuItem {
     async fn by_menu_uid(context: &Context, menu_uid: &str) -> Result<Vec<Self>, FieldError> {
         sqlx::query_as!(
             MenuItem,
-            "SELECT id, name, recipe_uid, menu_uid, type_uid, day FROM menu_item WHERE menu_uid = $1",
+            "SELECT id, name, order_flag, recipe_uid, menu_uid, type_uid, day FROM menu_item WHERE menu_uid = $1",
             menu_uid
         )
         .fetch_all(&context.conns.pool)
         .await
-        .map_err(|_err| FieldError::new("could not query database", graphql_value!(None)))
+        .map_err(|err| db_error(context, "could not query database", err))
     }
 }
 
@@ -670,6 +2165,10 @@ impl MenuItem {
         &self.name
     }
 
+    fn order_flag(&self) -> i32 {
+        self.order_flag
+    }
+
     fn day(&self) -> i32 {
         self.day
     }
@@ -679,7 +2178,7 @@ impl MenuItem {
             .menu_loader
             .load(self.menu_uid.clone())
             .await
-            .map_err(|_err| FieldError::new("item should always have menu", graphql_value!(None)))
+            .map_err(|err| err.field_error(context, "item should always have menu"))
     }
 
     async fn recipe(&self, context: &Context) -> Result<Recipe, FieldError> {
@@ -687,7 +2186,7 @@ impl MenuItem {
             .recipe_loader
             .load(self.recipe_uid.clone())
             .await
-            .map_err(|_err| FieldError::new("item should always have recipe", graphql_value!(None)))
+            .map_err(|err| err.field_error(context, "item should always have recipe"))
     }
 
     async fn meal_type(&self, context: &Context) -> Result<MealType, FieldError> {
@@ -695,27 +2194,84 @@ impl MenuItem {
             .meal_type_loader
             .load(self.type_uid.clone())
             .await
-            .map_err(|_err| {
-                FieldError::new("item should always have meal type", graphql_value!(None))
-            })
+            .map_err(|err| err.field_error(context, "item should always have meal type"))
     }
 }
 
-#[derive(Clone, Debug)]
-struct Menu {
-    id: i32,
-    uid: String,
-    name: String,
-    notes: String,
-    days: i32,
+/// Items planned for a single day of a menu.
+#[derive(GraphQLObject)]
+#[graphql(context = Context)]
+struct MenuDay {
+    day: i32,
+    items: Vec<MenuItem>,
 }
 
-impl Menu {
-    async fn all(context: &Context) -> Result<Vec<Self>, FieldError> {
-        sqlx::query_as!(Self, "SELECT id, uid, name, notes, days FROM menu")
-            .fetch_all(&context.conns.pool)
-            .await
-            .map_err(|_err| FieldError::new("could not query database", graphql_value!(None)))
+impl MenuDay {
+    /// Group a menu's items by day, ordering days and the items within them.
+    async fn by_menu_uid(context: &Context, menu_uid: &str) -> Result<Vec<Self>, FieldError> {
+        let mut days: std::collections::BTreeMap<i32, Vec<MenuItem>> = Default::default();
+        for item in MenuItem::by_menu_uid(context, menu_uid).await? {
+            days.entry(item.day).or_default().push(item);
+        }
+
+        Ok(days
+            .into_iter()
+            .map(|(day, mut items)| {
+                items.sort_by_key(|item| item.order_flag);
+                Self { day, items }
+            })
+            .collect())
+    }
+}
+
+/// A menu item placed on a calendar date.
+#[derive(GraphQLObject)]
+#[graphql(context = Context)]
+struct PlannedMeal {
+    date: chrono::NaiveDate,
+    item: MenuItem,
+}
+
+impl PlannedMeal {
+    /// Place a menu's items on dates starting from the given date. Menu days
+    /// are numbered from 1, which is placed on the start date, and menus
+    /// spanning more than a week continue on the following dates.
+    async fn by_menu_uid(
+        context: &Context,
+        menu_uid: &str,
+        start_date: chrono::NaiveDate,
+    ) -> Result<Vec<Self>, FieldError> {
+        let mut items = MenuItem::by_menu_uid(context, menu_uid).await?;
+        items.sort_by_key(|item| (item.day, item.order_flag));
+
+        Ok(items
+            .into_iter()
+            .map(|item| {
+                let offset = chrono::Duration::days(i64::from(item.day.max(1) - 1));
+                Self {
+                    date: start_date + offset,
+                    item,
+                }
+            })
+            .collect())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Menu {
+    id: i32,
+    uid: String,
+    name: String,
+    notes: String,
+    days: i32,
+}
+
+impl Menu {
+    async fn all(context: &Context) -> Result<Vec<Self>, FieldError> {
+        sqlx::query_as!(Self, "SELECT id, uid, name, notes, days FROM menu")
+            .fetch_all(&context.conns.pool)
+            .await
+            .map_err(|err| db_error(context, "could not query database", err))
     }
 }
 
@@ -740,6 +2296,16 @@ impl Menu {
     async fn items(&self, context: &Context) -> Result<Vec<MenuItem>, FieldError> {
         MenuItem::by_menu_uid(context, &self.uid).await
     }
+
+    /// The menu's items on calendar dates, with the first day of the menu on
+    /// the start date.
+    async fn planned_meals(
+        &self,
+        context: &Context,
+        start_date: chrono::NaiveDate,
+    ) -> Result<Vec<PlannedMeal>, FieldError> {
+        PlannedMeal::by_menu_uid(context, &self.uid, start_date).await
+    }
 }
 
 struct MenuBatcher(sqlx::Pool<sqlx::Postgres>);
@@ -758,13 +2324,7 @@ impl BatchFn<String, Result<Menu, DbError>> for MenuBatcher {
         .fetch_all(&self.0)
         .await;
 
-        match menus {
-            Ok(menus) => menus
-                .into_iter()
-                .map(|menu| (menu.uid.clone(), Ok(menu)))
-                .collect(),
-            Err(_err) => keys.iter().map(|k| (k.to_owned(), Err(DbError))).collect(),
-        }
+        batch_results(keys, menus, |menu| &menu.uid)
     }
 }
 
@@ -779,7 +2339,7 @@ impl Bookmark {
         sqlx::query_as!(Self, "SELECT id, title, url FROM bookmark")
             .fetch_all(&context.conns.pool)
             .await
-            .map_err(|_err| FieldError::new("could not query database", graphql_value!(None)))
+            .map_err(|err| db_error(context, "could not query database", err))
     }
 }
 
@@ -811,7 +2371,55 @@ impl Category {
         sqlx::query_as!(Self, r"SELECT id, uid, name, parent_uid FROM category")
             .fetch_all(&context.conns.pool)
             .await
-            .map_err(|_err| FieldError::new("could not query database", graphql_value!(None)))
+            .map_err(|err| db_error(context, "could not query database", err))
+    }
+}
+
+/// A category with the number of recipes in it, excluding recipes in the
+/// trash.
+#[derive(GraphQLObject)]
+#[graphql(context = Context)]
+struct CategoryCount {
+    category: Category,
+    recipe_count: i32,
+}
+
+impl CategoryCount {
+    async fn all(context: &Context) -> Result<Vec<Self>, FieldError> {
+        let rows = sqlx::query!(
+            r#"SELECT
+                category.id,
+                category.uid,
+                category.name,
+                category.parent_uid,
+                count(recipe_category.uid) "recipe_count!"
+            FROM
+                category
+            LEFT JOIN (
+                SELECT unnest(categories) uid FROM recipe WHERE NOT in_trash
+            ) recipe_category
+                ON category.uid = recipe_category.uid
+            GROUP BY
+                category.id
+            ORDER BY
+                category.name"#
+        )
+        .fetch_all(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not query database", err))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Self {
+                category: Category {
+                    id: row.id,
+                    uid: row.uid,
+                    name: row.name,
+                    parent_uid: row.parent_uid,
+                },
+                recipe_count: row.recipe_count as i32,
+            })
+            .collect())
     }
 }
 
@@ -832,9 +2440,7 @@ impl Category {
                 .load(parent_uid.clone())
                 .await
                 .map(Some)
-                .map_err(|_err| {
-                    FieldError::new("item should always have parent", graphql_value!(None))
-                })
+                .map_err(|err| err.field_error(context, "item should always have parent"))
         } else {
             Ok(None)
         }
@@ -861,13 +2467,7 @@ impl BatchFn<String, Result<Category, DbError>> for CategoryBatcher {
         .fetch_all(&self.0)
         .await;
 
-        match categories {
-            Ok(categories) => categories
-                .into_iter()
-                .map(|category| (category.uid.clone(), Ok(category)))
-                .collect(),
-            Err(_err) => keys.iter().map(|k| (k.to_owned(), Err(DbError))).collect(),
-        }
+        batch_results(keys, categories, |category| &category.uid)
     }
 }
 
@@ -883,7 +2483,7 @@ impl Photo {
         sqlx::query_as!(Self, r"SELECT id, filename, recipe_uid, hash FROM photo")
             .fetch_all(&context.conns.pool)
             .await
-            .map_err(|_err| FieldError::new("could not query database", graphql_value!(None)))
+            .map_err(|err| db_error(context, "could not query database", err))
     }
 
     async fn by_recipe_uid(context: &Context, recipe_uid: &str) -> Result<Vec<Self>, FieldError> {
@@ -894,7 +2494,7 @@ impl Photo {
         )
         .fetch_all(&context.conns.pool)
         .await
-        .map_err(|_err| FieldError::new("could not query database", graphql_value!(None)))
+        .map_err(|err| db_error(context, "could not query database", err))
     }
 }
 
@@ -917,7 +2517,7 @@ impl Photo {
             .recipe_loader
             .load(self.recipe_uid.clone())
             .await
-            .map_err(|_err| FieldError::new("item should always have recipe", graphql_value!(None)))
+            .map_err(|err| err.field_error(context, "item should always have recipe"))
     }
 }
 
@@ -934,7 +2534,7 @@ impl GroceryList {
         sqlx::query_as!(Self, r"SELECT id, uid, name, is_default FROM grocery_list")
             .fetch_all(&context.conns.pool)
             .await
-            .map_err(|_err| FieldError::new("could not query database", graphql_value!(None)))
+            .map_err(|err| db_error(context, "could not query database", err))
     }
 }
 
@@ -944,6 +2544,10 @@ impl GroceryList {
         self.id
     }
 
+    fn uid(&self) -> &str {
+        &self.uid
+    }
+
     fn name(&self) -> &str {
         &self.name
     }
@@ -973,13 +2577,7 @@ impl BatchFn<String, Result<GroceryList, DbError>> for GroceryListBatcher {
         .fetch_all(&self.0)
         .await;
 
-        match grocery_lists {
-            Ok(grocery_lists) => grocery_lists
-                .into_iter()
-                .map(|grocery_list| (grocery_list.uid.clone(), Ok(grocery_list)))
-                .collect(),
-            Err(_err) => keys.iter().map(|k| (k.to_owned(), Err(DbError))).collect(),
-        }
+        batch_results(keys, grocery_lists, |grocery_list| &grocery_list.uid)
     }
 }
 
@@ -994,7 +2592,7 @@ impl GroceryIngredient {
         sqlx::query_as!(Self, r"SELECT id, name, aisle_uid FROM grocery_ingredient")
             .fetch_all(&context.conns.pool)
             .await
-            .map_err(|_err| FieldError::new("could not query database", graphql_value!(None)))
+            .map_err(|err| db_error(context, "could not query database", err))
     }
 }
 
@@ -1015,9 +2613,7 @@ impl GroceryIngredient {
                 .load(aisle_uid.clone())
                 .await
                 .map(Some)
-                .map_err(|_err| {
-                    FieldError::new("item should always have aisle", graphql_value!(None))
-                })
+                .map_err(|err| err.field_error(context, "item should always have aisle"))
         } else {
             Ok(None)
         }
@@ -1032,12 +2628,59 @@ impl Query {
         Recipe::from_id(context, id).await
     }
 
-    async fn recipes(context: &Context) -> Result<Vec<Recipe>, FieldError> {
-        Recipe::all(context).await
+    #[graphql(deprecated = "Use `recipesConnection` to paginate results")]
+    async fn recipes(
+        context: &Context,
+        filter: Option<RecipeFilter>,
+        order_by: Option<RecipeOrder>,
+    ) -> Result<Vec<Recipe>, FieldError> {
+        Recipe::all(context, filter, order_by).await
+    }
+
+    async fn recipes_connection(
+        context: &Context,
+        first: Option<i32>,
+        after: Option<String>,
+        filter: Option<RecipeFilter>,
+        order_by: Option<RecipeOrder>,
+    ) -> Result<RecipeConnection, FieldError> {
+        RecipeConnection::load(context, first, after, filter, order_by).await
+    }
+
+    /// Recipes matching the search terms, best matches first. Up to `limit`
+    /// recipes are returned, 25 by default and at most 100.
+    async fn search_recipes(
+        context: &Context,
+        query: String,
+        limit: Option<i32>,
+    ) -> Result<Vec<Recipe>, FieldError> {
+        Recipe::search(context, &query, limit).await
+    }
+
+    async fn recipes_with_ingredient(
+        context: &Context,
+        term: String,
+    ) -> Result<Vec<Recipe>, FieldError> {
+        Recipe::with_ingredient(context, &term).await
+    }
+
+    async fn random_recipe(
+        context: &Context,
+        only_favorites: Option<bool>,
+    ) -> Result<Option<Recipe>, FieldError> {
+        Recipe::random(context, only_favorites.unwrap_or(false)).await
+    }
+
+    async fn quick_recipes(context: &Context, max_minutes: i32) -> Result<Vec<Recipe>, FieldError> {
+        Recipe::quick(context, max_minutes).await
     }
 
-    async fn meals(context: &Context) -> Result<Vec<Meal>, FieldError> {
-        Meal::all(context).await
+    async fn meals(
+        context: &Context,
+        from: Option<chrono::DateTime<chrono::Utc>>,
+        to: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<Vec<Meal>, FieldError> {
+        Meal::in_range(context, from, to).await
     }
 
     async fn groceries(context: &Context) -> Result<Vec<GroceryItem>, FieldError> {
@@ -1048,10 +2691,26 @@ impl Query {
         PantryItem::all(context).await
     }
 
+    /// Pantry items that are out of stock, for adding to a grocery list.
+    async fn restock_suggestions(context: &Context) -> Result<Vec<RestockSuggestion>, FieldError> {
+        RestockSuggestion::all(context).await
+    }
+
+    async fn expiring_pantry_items(
+        context: &Context,
+        within: i32,
+    ) -> Result<Vec<PantryItem>, FieldError> {
+        PantryItem::expiring_within(context, within).await
+    }
+
     async fn menus(context: &Context) -> Result<Vec<Menu>, FieldError> {
         Menu::all(context).await
     }
 
+    async fn menu_week(context: &Context, menu_uid: String) -> Result<Vec<MenuDay>, FieldError> {
+        MenuDay::by_menu_uid(context, &menu_uid).await
+    }
+
     async fn bookmarks(context: &Context) -> Result<Vec<Bookmark>, FieldError> {
         Bookmark::all(context).await
     }
@@ -1060,6 +2719,10 @@ impl Query {
         Category::all(context).await
     }
 
+    async fn categories_with_counts(context: &Context) -> Result<Vec<CategoryCount>, FieldError> {
+        CategoryCount::all(context).await
+    }
+
     async fn photos(context: &Context) -> Result<Vec<Photo>, FieldError> {
         Photo::all(context).await
     }
@@ -1071,49 +2734,976 @@ impl Query {
     async fn grocery_ingredients(context: &Context) -> Result<Vec<GroceryIngredient>, FieldError> {
         GroceryIngredient::all(context).await
     }
+
+    async fn recent_changes(
+        context: &Context,
+        since: chrono::DateTime<chrono::Utc>,
+        limit: Option<i32>,
+    ) -> Result<Vec<ChangeLogEntry>, FieldError> {
+        ChangeLogEntry::since(context, since, limit).await
+    }
+
+    async fn sync_status(context: &Context) -> Result<Vec<CollectionStatus>, FieldError> {
+        CollectionStatus::all(context).await
+    }
+
+    async fn recipes_needing_resync(context: &Context) -> Result<Vec<Recipe>, FieldError> {
+        Recipe::needing_resync(context).await
+    }
+
+    async fn account_stats(context: &Context) -> Result<Stats, FieldError> {
+        Stats::load(context).await
+    }
+}
+
+#[derive(juniper::GraphQLInputObject)]
+struct RecipeInput {
+    name: String,
+    ingredients: Option<String>,
+    directions: Option<String>,
+    notes: Option<String>,
+    /// The uids of categories the recipe belongs to.
+    categories: Option<Vec<String>>,
+    cook_time: Option<String>,
+    prep_time: Option<String>,
+    total_time: Option<String>,
+}
+
+impl From<RecipeInput> for paprika_client::PaprikaRecipe {
+    fn from(input: RecipeInput) -> Self {
+        let mut recipe = Self::new(input.name);
+        recipe.ingredients = input.ingredients.unwrap_or_default();
+        recipe.directions = input.directions.unwrap_or_default();
+        recipe.notes = input.notes.unwrap_or_default();
+        recipe.categories = input.categories.unwrap_or_default();
+        recipe.cook_time = input.cook_time;
+        recipe.prep_time = input.prep_time;
+        recipe.total_time = input.total_time;
+
+        recipe
+    }
 }
 
 struct Mutation;
 
 #[graphql_object(context = Context)]
 impl Mutation {
-    async fn sync(context: &Context) -> Result<bool, FieldError> {
-        let changes =
-            updates::check_for_updates(&context.conns.paprika, &context.conns.pool).await?;
-        let had_changes = changes.contains_key(&State::Added)
-            || changes.contains_key(&State::Deleted)
-            || changes.contains_key(&State::Changed);
+    async fn sync(context: &Context) -> Result<SyncResult, FieldError> {
+        let _guard = context.conns.sync_lock.lock().await;
+
+        let changes = updates::check_for_updates(
+            &context.conns.paprika,
+            &context.conns.pool,
+            &context.conns.sync_events,
+            context.conns.webhook.as_ref(),
+            &context.conns.shutdown,
+        )
+        .await?;
+
+        Ok(SyncResult::from(&changes))
+    }
+
+    async fn sync_collection(context: &Context, name: String) -> Result<SyncResult, FieldError> {
+        if !updates::COLLECTIONS.contains(&name.as_str()) {
+            return Err(FieldError::new(
+                format!(
+                    "unknown collection {}, expected one of: {}",
+                    name,
+                    updates::COLLECTIONS.join(", ")
+                ),
+                context.error_extensions(Some("UNKNOWN_COLLECTION")),
+            ));
+        }
+
+        let _guard = context.conns.sync_lock.lock().await;
+
+        let changes = updates::sync_collection(
+            &context.conns.paprika,
+            &context.conns.pool,
+            &context.conns.sync_events,
+            &name,
+            &context.conns.shutdown,
+        )
+        .await?;
+
+        Ok(SyncResult::from(&changes))
+    }
+
+    async fn create_recipe(context: &Context, input: RecipeInput) -> Result<Recipe, FieldError> {
+        let recipe = context
+            .conns
+            .paprika
+            .create_recipe(input.into())
+            .await
+            .map_err(|err| {
+                tracing::error!("could not create recipe: {:?}", err);
+                FieldError::new(
+                    format!("could not create recipe: {}", err),
+                    context.error_extensions(None),
+                )
+            })?;
+
+        let _guard = context.conns.sync_lock.lock().await;
+
+        updates::sync_recipe(&context.conns.paprika, &context.conns.pool, &recipe.uid).await?;
+
+        Recipe::from_uid(context, &recipe.uid)
+            .await?
+            .ok_or_else(|| {
+                FieldError::new(
+                    "created recipe was not found",
+                    context.error_extensions(None),
+                )
+            })
+    }
+
+    async fn set_aisle_purchased(
+        context: &Context,
+        list_uid: String,
+        aisle_uid: String,
+        purchased: bool,
+    ) -> Result<i32, FieldError> {
+        let changed = context
+            .conns
+            .paprika
+            .set_aisle_purchased(&list_uid, &aisle_uid, purchased)
+            .await?;
+
+        sqlx::query!(
+            "UPDATE grocery_item SET purchased = $3 WHERE list_uid = $1 AND aisle_uid = $2",
+            list_uid,
+            aisle_uid,
+            purchased
+        )
+        .execute(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not update database", err))?;
+
+        Ok(changed as i32)
+    }
+
+    async fn set_grocery_purchased(
+        context: &Context,
+        id: i32,
+        purchased: bool,
+    ) -> Result<Option<GroceryItem>, FieldError> {
+        let item = match GroceryItem::paprika_item(context, id).await? {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+
+        context
+            .conns
+            .paprika
+            .set_groceries_purchased(&[item], purchased)
+            .await?;
+
+        sqlx::query!(
+            "UPDATE grocery_item SET purchased = $2 WHERE id = $1",
+            id,
+            purchased
+        )
+        .execute(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not update database", err))?;
+
+        GroceryItem::from_id(context, id).await
+    }
+
+    /// Delete every purchased item on a grocery list, returning the number of
+    /// deleted items.
+    async fn clear_purchased(context: &Context, list_uid: String) -> Result<i32, FieldError> {
+        let deleted = context.conns.paprika.clear_purchased(&list_uid).await?;
+
+        sqlx::query!(
+            "DELETE FROM grocery_item WHERE list_uid = $1 AND purchased",
+            list_uid
+        )
+        .execute(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not update database", err))?;
+
+        Ok(deleted as i32)
+    }
+
+    /// Pin or unpin a recipe.
+    async fn set_recipe_pinned(
+        context: &Context,
+        id: i32,
+        pinned: bool,
+    ) -> Result<Option<Recipe>, FieldError> {
+        let recipe =
+            match Recipe::update_paprika(context, id, |recipe| recipe.is_pinned = pinned).await? {
+                Some(recipe) => recipe,
+                None => return Ok(None),
+            };
+
+        sqlx::query!(
+            "UPDATE recipe SET is_pinned = $2, hash = $3 WHERE id = $1",
+            id,
+            recipe.is_pinned,
+            recipe.hash
+        )
+        .execute(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not update database", err))?;
+
+        Recipe::from_id(context, id).await
+    }
+
+    /// Add or remove a recipe from favorites.
+    async fn set_recipe_favorite(
+        context: &Context,
+        id: i32,
+        favorite: bool,
+    ) -> Result<Option<Recipe>, FieldError> {
+        let recipe =
+            match Recipe::update_paprika(context, id, |recipe| recipe.on_favorites = favorite)
+                .await?
+            {
+                Some(recipe) => recipe,
+                None => return Ok(None),
+            };
+
+        sqlx::query!(
+            "UPDATE recipe SET on_favorites = $2, hash = $3 WHERE id = $1",
+            id,
+            recipe.on_favorites,
+            recipe.hash
+        )
+        .execute(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not update database", err))?;
+
+        Recipe::from_id(context, id).await
+    }
+
+    /// Move a recipe out of the trash. Recipes that aren't in the trash are
+    /// returned unchanged.
+    async fn restore_recipe(context: &Context, id: i32) -> Result<Option<Recipe>, FieldError> {
+        match Recipe::from_id(context, id).await? {
+            Some(recipe) if recipe.in_trash => (),
+            recipe => return Ok(recipe),
+        }
+
+        let recipe =
+            match Recipe::update_paprika(context, id, |recipe| recipe.in_trash = false).await? {
+                Some(recipe) => recipe,
+                None => return Ok(None),
+            };
+
+        sqlx::query!(
+            "UPDATE recipe SET in_trash = $2, hash = $3 WHERE id = $1",
+            id,
+            recipe.in_trash,
+            recipe.hash
+        )
+        .execute(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not update database", err))?;
+
+        Recipe::from_id(context, id).await
+    }
+
+    /// Add each ingredient of a recipe to a grocery list, returning the number
+    /// of added items.
+    async fn add_recipe_to_grocery_list(
+        context: &Context,
+        recipe_id: i32,
+        list_uid: String,
+    ) -> Result<Option<i32>, FieldError> {
+        let uid = sqlx::query_scalar!("SELECT uid FROM recipe WHERE id = $1", recipe_id)
+            .fetch_optional(&context.conns.pool)
+            .await
+            .map_err(|err| db_error(context, "could not query database", err))?;
+
+        let uid = match uid {
+            Some(uid) => uid,
+            None => return Ok(None),
+        };
+
+        let recipe = context.conns.paprika.recipe(&uid).await?;
+        let items = context
+            .conns
+            .paprika
+            .add_recipe_to_grocery_list(&recipe, &list_uid)
+            .await?;
+
+        let mut tx = context
+            .conns
+            .pool
+            .begin()
+            .await
+            .map_err(|err| db_error(context, "could not update database", err))?;
+
+        for item in &items {
+            sqlx::query!(
+                "INSERT INTO grocery_item (uid, recipe_uid, name, order_flag, purchased, aisle, ingredient, recipe, instruction, quantity, separate, aisle_uid, list_uid) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
+                item.uid,
+                item.recipe_uid,
+                item.name,
+                item.order_flag,
+                item.purchased,
+                item.aisle,
+                item.ingredient,
+                item.recipe,
+                item.instruction,
+                item.quantity,
+                item.separate,
+                item.aisle_uid,
+                item.list_uid
+            )
+            .execute(&mut tx)
+            .await
+            .map_err(|err| db_error(context, "could not update database", err))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|err| db_error(context, "could not update database", err))?;
+
+        Ok(Some(items.len() as i32))
+    }
+
+    /// Add a pantry item's ingredient to a grocery list, in the same aisle as
+    /// the pantry item.
+    async fn add_pantry_item_to_grocery_list(
+        context: &Context,
+        pantry_id: i32,
+        list_uid: String,
+    ) -> Result<Option<GroceryItem>, FieldError> {
+        let pantry_item = sqlx::query_as!(
+            paprika_client::PaprikaPantryItem,
+            r#"SELECT
+                uid,
+                ingredient,
+                aisle,
+                expiration_date,
+                has_expiration,
+                in_stock,
+                purchase_date,
+                quantity,
+                aisle_uid
+            FROM
+                pantry_item
+            WHERE
+                id = $1"#,
+            pantry_id
+        )
+        .fetch_optional(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not query database", err))?;
+
+        let pantry_item = match pantry_item {
+            Some(pantry_item) => pantry_item,
+            None => return Ok(None),
+        };
+
+        let item = paprika_client::grocery_item_for_pantry_item(&pantry_item, &list_uid);
+        context
+            .conns
+            .paprika
+            .upload_groceries(std::slice::from_ref(&item))
+            .await?;
+
+        let id = sqlx::query_scalar!(
+            "INSERT INTO grocery_item (uid, recipe_uid, name, order_flag, purchased, aisle, ingredient, recipe, instruction, quantity, separate, aisle_uid, list_uid) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13) RETURNING id",
+            item.uid,
+            item.recipe_uid,
+            item.name,
+            item.order_flag,
+            item.purchased,
+            item.aisle,
+            item.ingredient,
+            item.recipe,
+            item.instruction,
+            item.quantity,
+            item.separate,
+            item.aisle_uid,
+            item.list_uid
+        )
+        .fetch_one(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not update database", err))?;
+
+        GroceryItem::from_id(context, id).await
+    }
+
+    /// Set the order of grocery items to the order of the given IDs.
+    async fn reorder_grocery_items(context: &Context, ids: Vec<i32>) -> Result<bool, FieldError> {
+        let mut tx = context
+            .conns
+            .pool
+            .begin()
+            .await
+            .map_err(|err| db_error(context, "could not update database", err))?;
+
+        let mut items = Vec::with_capacity(ids.len());
+        for (position, id) in ids.into_iter().enumerate() {
+            let item = sqlx::query_as!(
+                paprika_client::PaprikaGroceryItem,
+                r#"UPDATE
+                    grocery_item
+                SET
+                    order_flag = $2
+                WHERE
+                    id = $1
+                RETURNING
+                    uid,
+                    recipe_uid,
+                    name,
+                    order_flag,
+                    purchased,
+                    aisle,
+                    ingredient,
+                    recipe,
+                    instruction,
+                    quantity,
+                    separate,
+                    aisle_uid,
+                    list_uid"#,
+                id,
+                position as i32
+            )
+            .fetch_optional(&mut tx)
+            .await
+            .map_err(|err| db_error(context, "could not update database", err))?;
+
+            match item {
+                Some(item) => items.push(item),
+                None => {
+                    return Err(ErrorCode::NotFound
+                        .field_error(context, &format!("grocery item {} does not exist", id)))
+                }
+            }
+        }
+
+        context.conns.paprika.upload_groceries(&items).await?;
+
+        tx.commit()
+            .await
+            .map_err(|err| db_error(context, "could not update database", err))?;
+
+        Ok(true)
+    }
+}
+
+/// Totals across the account for building a dashboard. Recipes in the trash
+/// are not counted.
+#[derive(GraphQLObject, Debug, Clone)]
+struct Stats {
+    recipe_count: i32,
+    /// Average rating of recipes that have been rated.
+    average_rating: Option<f64>,
+    favorite_count: i32,
+    /// Meals planned from the start of the current week, on Monday, until the
+    /// end of Sunday.
+    meals_this_week: i32,
+    unpurchased_grocery_count: i32,
+    in_stock_pantry_count: i32,
+}
+
+impl Stats {
+    async fn load(context: &Context) -> Result<Self, FieldError> {
+        let row = sqlx::query!(
+            r#"SELECT
+                (SELECT count(*) FROM recipe WHERE NOT in_trash) "recipe_count!",
+                (
+                    SELECT avg(rating)::float8 FROM recipe WHERE NOT in_trash AND rating > 0
+                ) average_rating,
+                (
+                    SELECT count(*) FROM recipe WHERE NOT in_trash AND on_favorites
+                ) "favorite_count!",
+                (
+                    SELECT count(*) FROM meal
+                    WHERE date >= date_trunc('week', now())
+                        AND date < date_trunc('week', now()) + interval '1 week'
+                ) "meals_this_week!",
+                (SELECT count(*) FROM grocery_item WHERE NOT purchased) "unpurchased_grocery_count!",
+                (SELECT count(*) FROM pantry_item WHERE in_stock) "in_stock_pantry_count!""#
+        )
+        .fetch_one(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not query database", err))?;
+
+        Ok(Self {
+            recipe_count: row.recipe_count as i32,
+            average_rating: row.average_rating,
+            favorite_count: row.favorite_count as i32,
+            meals_this_week: row.meals_this_week as i32,
+            unpurchased_grocery_count: row.unpurchased_grocery_count as i32,
+            in_stock_pantry_count: row.in_stock_pantry_count as i32,
+        })
+    }
+}
+
+/// Sync position and freshness of a Paprika collection.
+#[derive(GraphQLObject, Debug, Clone)]
+struct CollectionStatus {
+    name: String,
+    position: i32,
+    last_synced_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl CollectionStatus {
+    async fn all(context: &Context) -> Result<Vec<Self>, FieldError> {
+        sqlx::query_as!(
+            CollectionStatus,
+            "SELECT name, position, last_synced_at FROM status ORDER BY name"
+        )
+        .fetch_all(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not query database", err))
+    }
+}
+
+/// Kind of change made to an item during a sync.
+#[derive(juniper::GraphQLEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum ChangeState {
+    Added,
+    Changed,
+    Deleted,
+}
+
+impl ChangeState {
+    fn from_db(state: &str) -> Option<Self> {
+        match state {
+            "added" => Some(Self::Added),
+            "changed" => Some(Self::Changed),
+            "deleted" => Some(Self::Deleted),
+            _ => None,
+        }
+    }
+}
+
+const DEFAULT_CHANGE_LIMIT: i32 = 100;
+const MAX_CHANGE_LIMIT: i32 = 1000;
+
+/// An item that was changed during a sync.
+#[derive(GraphQLObject, Debug, Clone)]
+struct ChangeLogEntry {
+    id: i32,
+    collection: String,
+    uid: String,
+    state: ChangeState,
+    changed_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ChangeLogEntry {
+    async fn since(
+        context: &Context,
+        since: chrono::DateTime<chrono::Utc>,
+        limit: Option<i32>,
+    ) -> Result<Vec<Self>, FieldError> {
+        let limit = limit
+            .unwrap_or(DEFAULT_CHANGE_LIMIT)
+            .clamp(0, MAX_CHANGE_LIMIT);
+
+        let rows = sqlx::query!(
+            "SELECT id, collection, uid, state, changed_at
+            FROM change_log
+            WHERE changed_at >= $1
+            ORDER BY changed_at DESC, id DESC
+            LIMIT $2",
+            since,
+            limit as i64
+        )
+        .fetch_all(&context.conns.pool)
+        .await
+        .map_err(|err| db_error(context, "could not query database", err))?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let state = ChangeState::from_db(&row.state)?;
+
+                Some(Self {
+                    id: row.id,
+                    collection: row.collection,
+                    uid: row.uid,
+                    state,
+                    changed_at: row.changed_at,
+                })
+            })
+            .collect())
+    }
+}
+
+/// Number of items in each state after a sync.
+#[derive(GraphQLObject, Debug, Clone)]
+struct SyncResult {
+    added: i32,
+    changed: i32,
+    deleted: i32,
+    unchanged: i32,
+}
+
+impl From<&std::collections::HashMap<State, usize>> for SyncResult {
+    fn from(changes: &std::collections::HashMap<State, usize>) -> Self {
+        let count = |state| changes.get(&state).copied().unwrap_or_default() as i32;
+
+        Self {
+            added: count(State::Added),
+            changed: count(State::Changed),
+            deleted: count(State::Deleted),
+            unchanged: count(State::Equal),
+        }
+    }
+}
+
+/// A summary of the changes a sync applied to a collection.
+#[derive(GraphQLObject, Debug, Clone)]
+struct SyncChange {
+    collection: String,
+    added: i32,
+    changed: i32,
+    deleted: i32,
+}
+
+impl From<updates::CollectionChanges> for SyncChange {
+    fn from(changes: updates::CollectionChanges) -> Self {
+        Self {
+            collection: changes.collection,
+            added: changes.added as i32,
+            changed: changes.changed as i32,
+            deleted: changes.deleted as i32,
+        }
+    }
+}
+
+type SyncChangeStream =
+    std::pin::Pin<Box<dyn futures::Stream<Item = Result<SyncChange, FieldError>> + Send>>;
+
+struct Subscription;
+
+#[graphql_subscription(context = Context)]
+impl Subscription {
+    async fn sync_changes(context: &Context) -> SyncChangeStream {
+        let events = context.conns.sync_events.subscribe();
 
-        Ok(had_changes)
+        // Subscribers that fall behind skip the events they missed instead of
+        // holding up the sync.
+        let stream = futures::stream::unfold(events, |mut events| async move {
+            loop {
+                match events.recv().await {
+                    Ok(changes) => return Some((Ok(changes.into()), events)),
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("subscriber lagged, skipped {} sync events", skipped);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+
+        Box::pin(stream)
     }
 }
 
-type Schema = RootNode<'static, Query, Mutation, EmptySubscription<Context>>;
+type Schema = RootNode<'static, Query, Mutation, Subscription>;
 
 async fn graphiql_route() -> Result<HttpResponse, Error> {
-    graphiql_handler("/graphql", None).await
+    graphiql_handler("/graphql", Some("/subscriptions")).await
 }
 
 async fn playground_route() -> Result<HttpResponse, Error> {
-    playground_handler("/graphql", None).await
+    playground_handler("/graphql", Some("/subscriptions")).await
+}
+
+/// CORS configuration allowing the given origins, or any origin if none were
+/// configured.
+fn cors(allowed_origins: Option<&[String]>) -> Cors {
+    let cors = Cors::default()
+        .allowed_methods(vec!["POST", "GET"])
+        .allowed_headers(vec![
+            header::CONTENT_TYPE,
+            header::AUTHORIZATION,
+            header::HeaderName::from_static(request_id::HEADER),
+        ])
+        .expose_headers(vec![request_id::HEADER])
+        .max_age(3600);
+
+    match allowed_origins {
+        Some(origins) => origins
+            .iter()
+            .fold(cors, |cors, origin| cors.allowed_origin(origin)),
+        None => cors.allow_any_origin(),
+    }
+}
+
+/// Liveness check, succeeding whenever the server is able to respond.
+async fn health_route() -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({ "status": "ok" }))
+}
+
+/// Readiness check, succeeding only when the database is reachable.
+async fn ready_route(conns: web::Data<Connections>) -> HttpResponse {
+    match sqlx::query("SELECT 1").execute(&conns.pool).await {
+        Ok(_) => HttpResponse::Ok().json(serde_json::json!({ "status": "ok" })),
+        Err(err) => {
+            tracing::warn!("database is not ready: {:?}", err);
+            HttpResponse::ServiceUnavailable().json(serde_json::json!({ "status": "unavailable" }))
+        }
+    }
+}
+
+/// Content type for a photo, based on the extension of its filename.
+fn photo_content_type(filename: &str) -> &'static str {
+    let extension = filename
+        .rsplit_once('.')
+        .map(|(_name, extension)| extension.to_ascii_lowercase());
+
+    match extension.as_deref() {
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("heic") => "image/heic",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Download a photo from Paprika, verifying its contents before saving it to
+/// the cache directory.
+async fn cache_photo(conns: &Connections, uid: &str, hash: &str) -> anyhow::Result<Vec<u8>> {
+    let details = conns.paprika.photo(uid).await?;
+    let photo_url = details
+        .photo_url
+        .filter(|photo_url| !photo_url.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("photo {} has no url", uid))?;
+
+    save_photo(conns, uid, hash, photo_url).await
+}
+
+/// The result of fetching a recipe's main photo.
+enum RecipePhoto {
+    /// The photo data, now saved to the cache.
+    Current(Vec<u8>),
+    /// The recipe's photo has changed and now has this hash.
+    Changed(String),
+    /// The recipe no longer has a photo.
+    Missing,
+}
+
+/// Download a recipe's main photo if it still has the expected hash.
+async fn cache_recipe_photo(
+    conns: &Connections,
+    uid: &str,
+    hash: &str,
+) -> anyhow::Result<RecipePhoto> {
+    let recipe = conns.paprika.recipe(uid).await?;
+
+    if paprika_client::photo_is_stale(hash, &recipe) {
+        let current_hash = recipe.photo_hash.unwrap_or_default();
+        return Ok(RecipePhoto::Changed(current_hash.to_ascii_lowercase()));
+    }
+
+    let photo_url = match recipe.best_photo_url() {
+        Some(photo_url) if recipe.photo_hash.is_some() => photo_url.to_string(),
+        _ => return Ok(RecipePhoto::Missing),
+    };
+
+    save_photo(conns, uid, hash, photo_url)
+        .await
+        .map(RecipePhoto::Current)
+}
+
+/// Download a photo and save it to the cache directory if it matches the
+/// expected hash.
+async fn save_photo(
+    conns: &Connections,
+    uid: &str,
+    hash: &str,
+    photo_url: String,
+) -> anyhow::Result<Vec<u8>> {
+    static TEMP_FILE_ID: AtomicUsize = AtomicUsize::new(0);
+
+    let photo = conns.paprika.download_photo(photo_url).await?;
+    if !photo.matches_hash(hash) {
+        anyhow::bail!("photo {} did not match hash {}", uid, hash);
+    }
+
+    // Write to a temporary file first so a partially written photo is never
+    // served from the cache.
+    let temp_path = conns.photo_dir.join(format!(
+        "{}.{}.tmp",
+        hash,
+        TEMP_FILE_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+    tokio::fs::write(&temp_path, &photo.data).await?;
+    tokio::fs::rename(&temp_path, conns.photo_dir.join(hash)).await?;
+
+    Ok(photo.data)
+}
+
+/// A photo that may be served, either from the photos collection or the
+/// main photo of a recipe.
+struct PhotoSource {
+    uid: String,
+    filename: String,
+    hash: String,
+    recipe: bool,
+}
+
+impl PhotoSource {
+    async fn find(pool: &sqlx::Pool<sqlx::Postgres>, hash: &str) -> sqlx::Result<Option<Self>> {
+        let photo = sqlx::query!(
+            "SELECT uid, filename, hash FROM photo WHERE lower(hash) = lower($1) LIMIT 1",
+            hash
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        if let Some(photo) = photo {
+            return Ok(Some(Self {
+                uid: photo.uid,
+                filename: photo.filename,
+                hash: photo.hash,
+                recipe: false,
+            }));
+        }
+
+        let recipe = sqlx::query!(
+            r#"SELECT uid, coalesce(photo, '') AS "filename!", photo_hash AS "hash!" FROM recipe WHERE lower(photo_hash) = lower($1) LIMIT 1"#,
+            hash
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(recipe.map(|recipe| Self {
+            uid: recipe.uid,
+            filename: recipe.filename,
+            hash: recipe.hash,
+            recipe: true,
+        }))
+    }
 }
 
+/// Serve a photo by its hash, downloading it from Paprika the first time it
+/// is requested.
+///
+/// Recipe photos are checked against the recipe's current photo before being
+/// downloaded, redirecting to the current photo if it has changed.
+///
+/// Photos never change for a hash, but require authorization, so they may
+/// only be cached by the client and not by shared caches.
+async fn photo_route(
+    hash: web::Path<String>,
+    conns: web::Data<Connections>,
+) -> Result<HttpResponse, Error> {
+    let hash = hash.into_inner();
+    if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    let photo = PhotoSource::find(&conns.pool, &hash).await.map_err(|err| {
+        tracing::error!("could not look up photo: {:?}", err);
+        actix_web::error::ErrorInternalServerError("could not query database")
+    })?;
+
+    let photo = match photo {
+        Some(photo) => photo,
+        None => return Ok(HttpResponse::NotFound().finish()),
+    };
+
+    let hash = photo.hash.to_ascii_lowercase();
+    let data = match tokio::fs::read(conns.photo_dir.join(&hash)).await {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound && photo.recipe => {
+            tracing::debug!("recipe {} photo was not cached, downloading", photo.uid);
+            let recipe_photo = cache_recipe_photo(&conns, &photo.uid, &hash)
+                .await
+                .map_err(|err| {
+                    tracing::error!("could not download recipe {} photo: {:?}", photo.uid, err);
+                    actix_web::error::ErrorBadGateway("could not download photo")
+                })?;
+
+            match recipe_photo {
+                RecipePhoto::Current(data) => data,
+                RecipePhoto::Changed(current_hash) => {
+                    tracing::info!("recipe {} photo has changed", photo.uid);
+                    return Ok(HttpResponse::TemporaryRedirect()
+                        .insert_header((header::LOCATION, current_hash))
+                        .finish());
+                }
+                RecipePhoto::Missing => return Ok(HttpResponse::NotFound().finish()),
+            }
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            tracing::debug!("photo {} was not cached, downloading", photo.uid);
+            cache_photo(&conns, &photo.uid, &hash)
+                .await
+                .map_err(|err| {
+                    tracing::error!("could not download photo {}: {:?}", photo.uid, err);
+                    actix_web::error::ErrorBadGateway("could not download photo")
+                })?
+        }
+        Err(err) => {
+            tracing::error!("could not read cached photo {}: {:?}", photo.uid, err);
+            return Err(actix_web::error::ErrorInternalServerError(
+                "could not read photo",
+            ));
+        }
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(photo_content_type(&photo.filename))
+        .insert_header((
+            header::CACHE_CONTROL,
+            "private, max-age=31536000, immutable",
+        ))
+        .insert_header((header::ETAG, format!("\"{}\"", hash)))
+        .body(data))
+}
+
+/// Optional range of dates for meals in the calendar, as `YYYY-MM-DD`. The
+/// range includes `from` and excludes `to`.
+#[derive(serde::Deserialize)]
+struct CalendarQuery {
+    from: Option<chrono::NaiveDate>,
+    to: Option<chrono::NaiveDate>,
+}
+
+/// Serve planned meals as an iCalendar feed.
+async fn calendar_route(
+    query: web::Query<CalendarQuery>,
+    conns: web::Data<Connections>,
+) -> Result<HttpResponse, Error> {
+    let meals = calendar::CalendarMeal::in_range(&conns.pool, query.from, query.to)
+        .await
+        .map_err(|err| {
+            tracing::error!("could not load meals for calendar: {:?}", err);
+            actix_web::error::ErrorInternalServerError("could not query database")
+        })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .body(calendar::render(&meals, chrono::Utc::now())))
+}
+
+/// Execute a GraphQL request. A JSON array of operations is executed as a
+/// batch, returning an array of results in the same order, with every
+/// operation sharing one context so the loaders deduplicate queries across
+/// the whole batch.
 async fn graphql_route(
     req: HttpRequest,
     payload: web::Payload,
     schema: web::Data<Schema>,
     conns: web::Data<Connections>,
 ) -> Result<HttpResponse, Error> {
-    let context = Context {
-        recipe_loader: Loader::new(RecipeBatcher(conns.pool.clone())),
-        aisle_loader: Loader::new(AisleBatcher(conns.pool.clone())),
-        meal_type_loader: Loader::new(MealTypeBatcher(conns.pool.clone())),
-        grocery_list_loader: Loader::new(GroceryListBatcher(conns.pool.clone())),
-        menu_loader: Loader::new(MenuBatcher(conns.pool.clone())),
-        category_loader: Loader::new(CategoryBatcher(conns.pool.clone())),
-
-        conns: (*conns).clone(),
-    };
+    let context = Context::new((*conns).clone(), request_id::get(&req));
 
     graphql_handler(&schema, &context, req, payload).await
 }
+
+async fn subscriptions_route(
+    req: HttpRequest,
+    stream: web::Payload,
+    schema: web::Data<Schema>,
+    conns: web::Data<Connections>,
+    limits: web::Data<limits::QueryLimits>,
+) -> Result<HttpResponse, Error> {
+    let context = Context::new((*conns).clone(), request_id::get(&req));
+    let config = ConnectionConfig::new(context).with_keep_alive_interval(Duration::from_secs(15));
+
+    subscriptions::handler(req, stream, schema.into_inner(), config, **limits).await
+}
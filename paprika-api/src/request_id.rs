@@ -0,0 +1,122 @@
+use std::future::{ready, Ready};
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    error::InternalError,
+    http::header::{HeaderName, HeaderValue},
+    Error, HttpMessage, HttpRequest,
+};
+use futures::future::LocalBoxFuture;
+use tracing::Instrument;
+
+/// Header used to receive and return the request id.
+pub const HEADER: &str = "x-request-id";
+
+/// Longest request id accepted from a client before generating a new one.
+const MAX_LEN: usize = 128;
+
+/// The id of a request, stored in its extensions by [`RequestId`].
+#[derive(Clone, Debug)]
+struct Id(String);
+
+/// The id of a request, if it passed through the [`RequestId`] middleware.
+pub fn get(req: &HttpRequest) -> Option<String> {
+    req.extensions().get::<Id>().map(|id| id.0.clone())
+}
+
+/// Middleware tagging each request with an id, taken from the `X-Request-Id`
+/// header or generated if absent. Everything logged while handling the
+/// request is in a span with the id, and it's returned in the `X-Request-Id`
+/// header of every response, including errors, so responses can be matched
+/// with logs. Handlers can read the id with [`get`].
+#[derive(Clone, Copy)]
+pub struct RequestId;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestId
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestIdMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdMiddleware { service }))
+    }
+}
+
+pub struct RequestIdMiddleware<S> {
+    service: S,
+}
+
+/// The id provided by the client, if it's short and only contains visible
+/// ASCII characters.
+fn client_request_id(req: &ServiceRequest) -> Option<String> {
+    req.headers()
+        .get(HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_LEN)
+        .filter(|id| id.chars().all(|c| c.is_ascii_graphic()))
+        .map(ToString::to_string)
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let request_id =
+            client_request_id(&req).unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        req.extensions_mut().insert(Id(request_id.clone()));
+
+        let span = tracing::info_span!(
+            "request",
+            request_id = %request_id,
+            method = %req.method(),
+            path = %req.path()
+        );
+
+        let fut = span.in_scope(|| self.service.call(req));
+
+        Box::pin(
+            async move {
+                let header = HeaderValue::from_str(&request_id)
+                    .map(|value| (HeaderName::from_static(HEADER), value));
+
+                match fut.await {
+                    Ok(mut resp) => {
+                        if let Ok((name, value)) = header {
+                            resp.headers_mut().insert(name, value);
+                        }
+
+                        Ok(resp)
+                    }
+                    // Errors are turned into responses by actix later, so the
+                    // header is added to the error's response instead.
+                    Err(err) => {
+                        let mut resp = err.error_response();
+                        if let Ok((name, value)) = header {
+                            resp.headers_mut().insert(name, value);
+                        }
+
+                        Err(InternalError::from_response(err, resp).into())
+                    }
+                }
+            }
+            .instrument(span),
+        )
+    }
+}
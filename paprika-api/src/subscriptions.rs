@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use actix_web::{http::header, web, Error, HttpRequest, HttpResponse};
+use futures::{SinkExt, StreamExt};
+use juniper::{DefaultScalarValue, GraphQLError, RuleError};
+use juniper_graphql_ws::{ArcSchema, ClientMessage, Connection, ConnectionConfig, ServerMessage};
+
+use crate::{limits::QueryLimits, Context, Schema};
+
+/// Subprotocol spoken by juniper_graphql_ws, the one from
+/// subscriptions-transport-ws.
+const PROTOCOL: &str = "graphql-ws";
+
+/// Serve GraphQL subscriptions over a websocket.
+///
+/// Messages from the client are decoded and passed to a juniper_graphql_ws
+/// connection, and everything the connection produces is sent back as text
+/// frames. The connection is closed when either side finishes.
+///
+/// Operations are checked against the query limits before they start, and
+/// rejected operations get an error message instead of being executed.
+pub async fn handler(
+    req: HttpRequest,
+    stream: web::Payload,
+    schema: Arc<Schema>,
+    config: ConnectionConfig<Context>,
+    limits: QueryLimits,
+) -> Result<HttpResponse, Error> {
+    let (mut response, mut session, mut messages) = actix_ws::handle(&req, stream)?;
+
+    let (mut sink, mut reactions) = Connection::new(ArcSchema(schema.clone()), config).split();
+
+    let mut replies = session.clone();
+    actix_web::rt::spawn(async move {
+        while let Some(msg) = messages.next().await {
+            let msg = match msg {
+                Ok(actix_ws::Message::Text(text)) => {
+                    match serde_json::from_str::<ClientMessage<DefaultScalarValue>>(&text) {
+                        Ok(msg) => msg,
+                        Err(err) => {
+                            tracing::debug!("could not decode subscription message: {}", err);
+                            continue;
+                        }
+                    }
+                }
+                Ok(actix_ws::Message::Ping(bytes)) => {
+                    if replies.pong(&bytes).await.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                Ok(actix_ws::Message::Close(_)) | Err(_) => ClientMessage::ConnectionTerminate,
+                Ok(_) => continue,
+            };
+
+            if let ClientMessage::Start { id, payload } = &msg {
+                if let Err(message) = limits.check(&schema, &payload.query) {
+                    tracing::debug!("rejecting subscription query: {}", message);
+
+                    let error: ServerMessage<DefaultScalarValue> = ServerMessage::Error {
+                        id: id.clone(),
+                        payload: GraphQLError::ValidationError(vec![RuleError::new(&message, &[])])
+                            .into(),
+                    };
+                    let text = serde_json::to_string(&error).expect("error should serialize");
+                    if replies.text(text).await.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+            }
+
+            let terminate = matches!(msg, ClientMessage::ConnectionTerminate);
+            if sink.send(msg).await.is_err() || terminate {
+                break;
+            }
+        }
+    });
+
+    actix_web::rt::spawn(async move {
+        while let Some(msg) = reactions.next().await {
+            let text = match serde_json::to_string(&msg) {
+                Ok(text) => text,
+                Err(err) => {
+                    tracing::error!("could not encode subscription message: {}", err);
+                    continue;
+                }
+            };
+
+            if session.text(text).await.is_err() {
+                return;
+            }
+        }
+
+        let _ = session.close(None).await;
+    });
+
+    response.headers_mut().insert(
+        header::SEC_WEBSOCKET_PROTOCOL,
+        header::HeaderValue::from_static(PROTOCOL),
+    );
+
+    Ok(response)
+}
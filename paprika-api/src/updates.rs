@@ -1,10 +1,15 @@
 use std::{
     collections::{HashMap, HashSet},
-    convert::TryInto,
+    convert::{TryFrom, TryInto},
 };
 
 use futures::TryStreamExt;
 use paprika_client::*;
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
+
+use crate::webhook::Webhook;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum State {
@@ -14,21 +19,101 @@ pub enum State {
     Equal,
 }
 
+impl State {
+    /// Name of the state as recorded in the change log.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Deleted => "deleted",
+            Self::Changed => "changed",
+            Self::Equal => "equal",
+        }
+    }
+}
+
+/// Changes applied to a single collection during a sync.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CollectionChanges {
+    pub collection: String,
+    pub added: usize,
+    pub changed: usize,
+    pub deleted: usize,
+}
+
+impl CollectionChanges {
+    fn new(collection: &str, changes: &HashMap<State, usize>) -> Self {
+        let count = |state| changes.get(&state).copied().unwrap_or_default();
+
+        Self {
+            collection: collection.to_string(),
+            added: count(State::Added),
+            changed: count(State::Changed),
+            deleted: count(State::Deleted),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added == 0 && self.changed == 0 && self.deleted == 0
+    }
+}
+
+/// Progress of a sync, reported as each collection completes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncProgress {
+    /// The name of the completed collection.
+    pub collection: String,
+    /// Number of items processed in the collection, or zero if it was
+    /// already up to date.
+    pub items: usize,
+    /// Number of collections completed so far, including this one.
+    pub completed: usize,
+    /// Number of collections in the sync.
+    pub total: usize,
+}
+
 /// Attempt to sync database with Paprika's current state.
+///
+/// Changes to each collection are published to `events` once the sync has
+/// been committed, and sent to the webhook if there were any. Publishing never
+/// waits on subscribers or the webhook.
+///
+/// Once `cancel` is cancelled the sync stops before the next item and returns
+/// an error, leaving the database unchanged.
 pub async fn check_for_updates(
     paprika: &PaprikaClient,
     pool: &sqlx::Pool<sqlx::Postgres>,
+    events: &tokio::sync::broadcast::Sender<CollectionChanges>,
+    webhook: Option<&Webhook>,
+    cancel: &CancellationToken,
 ) -> anyhow::Result<HashMap<State, usize>> {
+    check_for_updates_with_progress(paprika, pool, events, webhook, cancel, |_progress| ()).await
+}
+
+/// Sync the same as [`check_for_updates`], calling `progress` as each
+/// collection completes.
+pub async fn check_for_updates_with_progress<F>(
+    paprika: &PaprikaClient,
+    pool: &sqlx::Pool<sqlx::Postgres>,
+    events: &tokio::sync::broadcast::Sender<CollectionChanges>,
+    webhook: Option<&Webhook>,
+    cancel: &CancellationToken,
+    progress: F,
+) -> anyhow::Result<HashMap<State, usize>>
+where
+    F: Fn(&SyncProgress) + Send + Sync,
+{
     let status: HashMap<String, i32> = paprika.status().await?.try_into()?;
+    let total = status.len();
 
     let mut changes = HashMap::with_capacity(4);
+    let mut collection_changes = Vec::new();
 
     let mut tx = pool.begin().await?;
     sqlx::query!("SET CONSTRAINTS ALL DEFERRED")
         .execute(&mut tx)
         .await?;
 
-    for (name, position) in status {
+    for (index, (name, position)) in status.into_iter().enumerate() {
         let database_position =
             sqlx::query_scalar!("SELECT position FROM status WHERE name = $1", name)
                 .fetch_optional(pool)
@@ -37,36 +122,35 @@ pub async fn check_for_updates(
         let matches_latest =
             matches!(database_position, Some(database_position) if position == database_position);
 
-        if !matches_latest {
+        let items = if !matches_latest {
             tracing::info!("section {} needs update", name);
-            let item_changes = match name.as_str() {
-                "menus" => update_collection::<PaprikaMenu>(paprika, &mut tx).await?,
-                "photos" => update_collection::<PaprikaPhoto>(paprika, &mut tx).await?,
-                "mealtypes" => update_collection::<PaprikaMealType>(paprika, &mut tx).await?,
-                "recipes" => update_collection::<PaprikaRecipeHash>(paprika, &mut tx).await?,
-                "pantry" => update_collection::<PaprikaPantryItem>(paprika, &mut tx).await?,
-                "meals" => update_collection::<PaprikaMeal>(paprika, &mut tx).await?,
-                "groceryingredients" => {
-                    update_collection::<PaprikaGroceryIngredient>(paprika, &mut tx).await?
-                }
-                "groceries" => update_collection::<PaprikaGroceryItem>(paprika, &mut tx).await?,
-                "groceryaisles" => update_collection::<PaprikaAisle>(paprika, &mut tx).await?,
-                "grocerylists" => update_collection::<PaprikaGroceryList>(paprika, &mut tx).await?,
-                "bookmarks" => update_collection::<PaprikaBookmark>(paprika, &mut tx).await?,
-                "menuitems" => update_collection::<PaprikaMenuItem>(paprika, &mut tx).await?,
-                "categories" => update_collection::<PaprikaCategory>(paprika, &mut tx).await?,
-                _ => unreachable!("unknown paprika changed item"),
-            };
+            let item_changes = update_named_collection(paprika, &mut tx, &name, cancel).await?;
+
+            let collection = CollectionChanges::new(&name, &item_changes);
+            if !collection.is_empty() {
+                collection_changes.push(collection);
+            }
 
+            let items = item_changes.values().sum();
             for (state, count) in item_changes {
                 *changes.entry(state).or_default() += count;
             }
+
+            items
         } else {
             tracing::info!("section {} is up to date", name);
+            0
         };
 
         tracing::info!("updated {}", name);
-        sqlx::query!("INSERT INTO status (name, position) VALUES ($1, $2) ON CONFLICT (name) DO UPDATE SET position = EXCLUDED.position", name, position).execute(pool).await?;
+        sqlx::query!("INSERT INTO status (name, position, last_synced_at) VALUES ($1, $2, now()) ON CONFLICT (name) DO UPDATE SET position = EXCLUDED.position, last_synced_at = EXCLUDED.last_synced_at", name, position).execute(&mut tx).await?;
+
+        progress(&SyncProgress {
+            collection: name,
+            items,
+            completed: index + 1,
+            total,
+        });
     }
 
     PaprikaRecipeHash::pre_commit(paprika, &mut tx).await?;
@@ -75,6 +159,141 @@ pub async fn check_for_updates(
 
     tracing::debug!("observed changes: {:?}", changes);
 
+    if let Some(webhook) = webhook.filter(|_| !collection_changes.is_empty()) {
+        webhook.notify(&collection_changes);
+    }
+
+    for collection in collection_changes {
+        // Sending only fails when there are no subscribers.
+        let _ = events.send(collection);
+    }
+
+    Ok(changes)
+}
+
+/// Sync a single collection with Paprika's current state, updating its
+/// status position. Cancellation is handled the same as in
+/// [`check_for_updates`].
+pub async fn sync_collection(
+    paprika: &PaprikaClient,
+    pool: &sqlx::Pool<sqlx::Postgres>,
+    events: &tokio::sync::broadcast::Sender<CollectionChanges>,
+    name: &str,
+    cancel: &CancellationToken,
+) -> anyhow::Result<HashMap<State, usize>> {
+    if !COLLECTIONS.contains(&name) {
+        anyhow::bail!("unknown collection {}", name);
+    }
+
+    let status: HashMap<String, i32> = paprika.status().await?.try_into()?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query!("SET CONSTRAINTS ALL DEFERRED")
+        .execute(&mut tx)
+        .await?;
+
+    let changes = update_named_collection(paprika, &mut tx, name, cancel).await?;
+
+    if let Some(position) = status.get(name) {
+        sqlx::query!("INSERT INTO status (name, position, last_synced_at) VALUES ($1, $2, now()) ON CONFLICT (name) DO UPDATE SET position = EXCLUDED.position, last_synced_at = EXCLUDED.last_synced_at", name, position).execute(&mut tx).await?;
+    }
+
+    PaprikaRecipeHash::pre_commit(paprika, &mut tx).await?;
+
+    tx.commit().await?;
+
+    let collection = CollectionChanges::new(name, &changes);
+    if !collection.is_empty() {
+        let _ = events.send(collection);
+    }
+
+    Ok(changes)
+}
+
+/// Fetch a single recipe from Paprika and store it, replacing any existing
+/// copy.
+pub async fn sync_recipe(
+    paprika: &PaprikaClient,
+    pool: &sqlx::Pool<sqlx::Postgres>,
+    uid: &str,
+) -> anyhow::Result<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::query!("SET CONSTRAINTS ALL DEFERRED")
+        .execute(&mut tx)
+        .await?;
+
+    // The current hash isn't known, so an empty one is used to make sure the
+    // recipe is always downloaded.
+    let item = PaprikaRecipeHash {
+        uid: uid.to_string(),
+        hash: String::new(),
+    };
+
+    let span = tracing::info_span!("sync_item", collection = "recipes", uid);
+    async {
+        let state = PaprikaRecipeHash::on_change(paprika, &mut tx, &item).await?;
+
+        if state != State::Equal {
+            log_change(&mut tx, "recipes", uid, state).await?;
+        }
+
+        Ok::<_, anyhow::Error>(())
+    }
+    .instrument(span)
+    .await?;
+
+    PaprikaRecipeHash::pre_commit(paprika, &mut tx).await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Names of all collections that may be synced.
+pub const COLLECTIONS: &[&str] = &[
+    "bookmarks",
+    "categories",
+    "groceries",
+    "groceryaisles",
+    "groceryingredients",
+    "grocerylists",
+    "meals",
+    "mealtypes",
+    "menuitems",
+    "menus",
+    "pantry",
+    "photos",
+    "recipes",
+];
+
+/// Update a collection by the name Paprika uses for it in the sync status.
+async fn update_named_collection(
+    paprika: &PaprikaClient,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    name: &str,
+    cancel: &CancellationToken,
+) -> anyhow::Result<HashMap<State, usize>> {
+    let changes = match name {
+        "menus" => update_collection::<PaprikaMenu>(paprika, tx, name, cancel).await?,
+        "photos" => update_collection::<PaprikaPhoto>(paprika, tx, name, cancel).await?,
+        "mealtypes" => update_collection::<PaprikaMealType>(paprika, tx, name, cancel).await?,
+        "recipes" => update_collection::<PaprikaRecipeHash>(paprika, tx, name, cancel).await?,
+        "pantry" => update_collection::<PaprikaPantryItem>(paprika, tx, name, cancel).await?,
+        "meals" => update_collection::<PaprikaMeal>(paprika, tx, name, cancel).await?,
+        "groceryingredients" => {
+            update_collection::<PaprikaGroceryIngredient>(paprika, tx, name, cancel).await?
+        }
+        "groceries" => update_collection::<PaprikaGroceryItem>(paprika, tx, name, cancel).await?,
+        "groceryaisles" => update_collection::<PaprikaAisle>(paprika, tx, name, cancel).await?,
+        "grocerylists" => {
+            update_collection::<PaprikaGroceryList>(paprika, tx, name, cancel).await?
+        }
+        "bookmarks" => update_collection::<PaprikaBookmark>(paprika, tx, name, cancel).await?,
+        "menuitems" => update_collection::<PaprikaMenuItem>(paprika, tx, name, cancel).await?,
+        "categories" => update_collection::<PaprikaCategory>(paprika, tx, name, cancel).await?,
+        _ => anyhow::bail!("unknown paprika collection {}", name),
+    };
+
     Ok(changes)
 }
 
@@ -85,23 +304,27 @@ trait UpdateItem: Sized {
     ) -> anyhow::Result<Vec<Self>>;
     async fn current_items(paprika: &PaprikaClient) -> anyhow::Result<Vec<Self>>;
 
+    /// Store a new item, returning the state that was applied. Items that
+    /// aren't stored are reported as [`State::Equal`].
     async fn on_add(
         paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()>;
+    ) -> anyhow::Result<State>;
 
+    /// Update a stored item, returning the state that was applied.
     async fn on_change(
         paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()>;
+    ) -> anyhow::Result<State>;
 
+    /// Remove a stored item, returning the state that was applied.
     async fn on_delete(
         paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         old_item: &Self,
-    ) -> anyhow::Result<()>;
+    ) -> anyhow::Result<State>;
 
     async fn pre_commit(
         _paprika: &PaprikaClient,
@@ -111,13 +334,35 @@ trait UpdateItem: Sized {
     }
 }
 
-/// Update a collection to match Paprika's current state.
+/// Record a change to an item in the change log.
+async fn log_change(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    collection: &str,
+    uid: &str,
+    state: State,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        "INSERT INTO change_log (collection, uid, state) VALUES ($1, $2, $3)",
+        collection,
+        uid,
+        state.as_str()
+    )
+    .execute(tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Update a collection to match Paprika's current state, recording each
+/// change in the change log.
 async fn update_collection<C>(
     paprika: &PaprikaClient,
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    collection: &str,
+    cancel: &CancellationToken,
 ) -> anyhow::Result<HashMap<State, usize>>
 where
-    C: PaprikaId + Eq + UpdateItem,
+    C: PaprikaId + Eq + ContentEq + UpdateItem,
 {
     tracing::debug!("updating collection");
 
@@ -142,47 +387,81 @@ where
         .collect();
     tracing::debug!("found {} unique items", known_items.len());
 
+    // Items with the same content, such as ones that were only reordered, are
+    // treated as unchanged, but are still updated so the new values are stored.
     let item_states: Vec<_> = known_items
         .iter()
         .map(|item| {
-            let state = match (existing_items.get(*item), current_items.get(*item)) {
+            let (state, reordered) = match (existing_items.get(*item), current_items.get(*item)) {
                 (Some(existing), Some(current)) => {
                     if existing.eq(current) {
-                        State::Equal
+                        (State::Equal, false)
+                    } else if existing.content_eq(current) {
+                        (State::Equal, true)
                     } else {
-                        State::Changed
+                        (State::Changed, false)
                     }
                 }
-                (Some(_existing), None) => State::Deleted,
-                (None, Some(_current)) => State::Added,
+                (Some(_existing), None) => (State::Deleted, false),
+                (None, Some(_current)) => (State::Added, false),
                 _ => unreachable!("item must have appeared in some state"),
             };
 
-            (item, state)
+            (item, state, reordered)
         })
         .collect();
 
     let mut changes: HashMap<State, usize> = HashMap::with_capacity(4);
 
-    for (id, state) in item_states {
-        match state {
-            State::Added => {
-                tracing::info!("item {} was added", id);
-                let item = current_items.get(*id).unwrap();
-                C::on_add(paprika, tx, item).await?;
-            }
-            State::Changed => {
-                tracing::info!("item {} was changed", id);
-                let item = current_items.get(*id).unwrap();
-                C::on_change(paprika, tx, item).await?;
-            }
-            State::Deleted => {
-                tracing::info!("item {} was deleted", id);
-                let item = existing_items.get(*id).unwrap();
-                C::on_delete(paprika, tx, item).await?;
+    for (id, state, reordered) in item_states {
+        if cancel.is_cancelled() {
+            anyhow::bail!("sync of {} was cancelled", collection);
+        }
+
+        let span = tracing::info_span!(
+            "sync_item",
+            collection,
+            uid = id.as_str(),
+            state = state.as_str()
+        );
+
+        let state = async {
+            let state = match state {
+                State::Added => {
+                    tracing::info!("item {} was added", id);
+                    let item = current_items.get(*id).unwrap();
+                    C::on_add(paprika, tx, item).await?
+                }
+                State::Changed => {
+                    tracing::info!("item {} was changed", id);
+                    let item = current_items.get(*id).unwrap();
+                    C::on_change(paprika, tx, item).await?
+                }
+                State::Deleted => {
+                    tracing::info!("item {} was deleted", id);
+                    let item = existing_items.get(*id).unwrap();
+                    C::on_delete(paprika, tx, item).await?
+                }
+                _ if reordered => {
+                    tracing::info!("item {} was reordered", id);
+                    let item = current_items.get(*id).unwrap();
+                    C::on_change(paprika, tx, item).await?;
+                    State::Equal
+                }
+                _ => {
+                    tracing::info!("item {} was unchanged", id);
+                    State::Equal
+                }
+            };
+
+            if state != State::Equal {
+                log_change(tx, collection, id, state).await?;
             }
-            _ => tracing::info!("item {} was unchanged", id),
+
+            Ok::<_, anyhow::Error>(state)
         }
+        .instrument(span)
+        .await?;
 
         *changes.entry(state).or_default() += 1;
     }
@@ -190,20 +469,141 @@ where
     Ok(changes)
 }
 
+/// If recipes in the trash should be removed instead of stored, controlled by
+/// the `SKIP_TRASHED_RECIPES` environment variable. Trashed recipes are kept
+/// by default.
+///
+/// Only the hash of a skipped recipe is stored, so it's downloaded again once
+/// it changes, such as when the recipe is restored.
+fn skip_trashed_recipes() -> bool {
+    matches!(
+        std::env::var("SKIP_TRASHED_RECIPES").as_deref(),
+        Ok("1") | Ok("true")
+    )
+}
+
+/// A recipe's duration in minutes, if it could be parsed.
+pub fn duration_minutes(duration: Option<&str>) -> Option<i32> {
+    duration
+        .and_then(parse_duration_minutes)
+        .and_then(|minutes| i32::try_from(minutes).ok())
+}
+
+/// Parse durations of recipes stored before they were parsed on save. Each
+/// recipe is only attempted once, even if its durations couldn't be parsed.
+pub async fn backfill_durations(pool: &sqlx::Pool<sqlx::Postgres>) -> anyhow::Result<()> {
+    let mut tx = pool.begin().await?;
+
+    let unparsed = sqlx::query!(
+        "SELECT uid, cook_time, prep_time, total_time FROM recipe WHERE NOT durations_parsed"
+    )
+    .fetch_all(&mut tx)
+    .await?;
+
+    if unparsed.is_empty() {
+        return Ok(());
+    }
+
+    tracing::info!("parsing durations of {} recipes", unparsed.len());
+
+    for recipe in unparsed {
+        sqlx::query!(
+            "UPDATE recipe SET cook_time_minutes = $2, prep_time_minutes = $3, total_time_minutes = $4, durations_parsed = true WHERE uid = $1",
+            recipe.uid,
+            duration_minutes(recipe.cook_time.as_deref()),
+            duration_minutes(recipe.prep_time.as_deref()),
+            duration_minutes(recipe.total_time.as_deref())
+        )
+        .execute(&mut tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Store a downloaded recipe that isn't in the database yet.
+async fn insert_recipe(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    recipe: &PaprikaRecipe,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        "INSERT INTO recipe (categories, cook_time, created, description, difficulty, directions, hash, image_url, in_trash, ingredients, is_pinned, name, notes, on_favorites, on_grocery_list, photo, photo_hash, photo_large, photo_url, prep_time, rating, scale, servings, source, source_url, total_time, uid, cook_time_minutes, nutritional_info, total_time_minutes, prep_time_minutes)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28, $29, $30, $31)",
+        &recipe.categories,
+        recipe.cook_time,
+        recipe.created,
+        recipe.description,
+        recipe.difficulty,
+        recipe.directions,
+        recipe.hash,
+        recipe.image_url,
+        recipe.in_trash,
+        recipe.ingredients,
+        recipe.is_pinned,
+        recipe.name,
+        recipe.notes,
+        recipe.on_favorites,
+        recipe.on_grocery_list,
+        recipe.photo,
+        recipe.photo_hash,
+        recipe.photo_large,
+        recipe.photo_url,
+        recipe.prep_time,
+        recipe.rating,
+        recipe.scale,
+        recipe.servings,
+        recipe.source,
+        recipe.source_url,
+        recipe.total_time,
+        recipe.uid,
+        duration_minutes(recipe.cook_time.as_deref()),
+        recipe.nutritional_info,
+        duration_minutes(recipe.total_time.as_deref()),
+        duration_minutes(recipe.prep_time.as_deref()),
+    )
+    .execute(tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Remember a trashed recipe that was skipped instead of stored, so it isn't
+/// downloaded again until its hash changes.
+async fn skip_recipe(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    recipe: &PaprikaRecipe,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        "INSERT INTO skipped_recipe (uid, hash) VALUES ($1, $2) ON CONFLICT (uid) DO UPDATE SET hash = EXCLUDED.hash",
+        recipe.uid,
+        recipe.hash
+    )
+    .execute(tx)
+    .await?;
+
+    Ok(())
+}
+
 #[async_trait::async_trait]
 impl UpdateItem for PaprikaRecipeHash {
+    /// Stored recipes, along with skipped trashed recipes so they compare as
+    /// equal while their hash is unchanged.
     async fn existing_items(
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> anyhow::Result<Vec<Self>> {
-        let recipes = sqlx::query!("SELECT uid, hash FROM recipe")
-            .map(|row| PaprikaRecipeHash {
-                uid: row.uid,
-                hash: row.hash,
-            })
-            .fetch_all(tx)
-            .await?
-            .into_iter()
-            .collect();
+        let recipes = sqlx::query!(
+            r#"SELECT uid AS "uid!", hash AS "hash!" FROM recipe UNION ALL SELECT uid, hash FROM skipped_recipe"#
+        )
+        .map(|row| PaprikaRecipeHash {
+            uid: row.uid,
+            hash: row.hash,
+        })
+        .fetch_all(tx)
+        .await?
+        .into_iter()
+        .collect();
 
         Ok(recipes)
     }
@@ -217,53 +617,80 @@ impl UpdateItem for PaprikaRecipeHash {
         paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         let recipe = paprika.recipe(&new_item.uid).await?;
 
-        sqlx::query!(
-            "INSERT INTO recipe (categories, cook_time, created, description, difficulty, directions, hash, image_url, in_trash, ingredients, is_pinned, name, notes, on_favorites, on_grocery_list, photo, photo_hash, photo_large, photo_url, prep_time, rating, scale, servings, source, source_url, total_time, uid)
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27)",
-            &recipe.categories,
-            recipe.cook_time,
-            recipe.created,
-            recipe.description,
-            recipe.difficulty,
-            recipe.directions,
-            recipe.hash,
-            recipe.image_url,
-            recipe.in_trash,
-            recipe.ingredients,
-            recipe.is_pinned,
-            recipe.name,
-            recipe.notes,
-            recipe.on_favorites,
-            recipe.on_grocery_list,
-            recipe.photo,
-            recipe.photo_hash,
-            recipe.photo_large,
-            recipe.photo_url,
-            recipe.prep_time,
-            recipe.rating,
-            recipe.scale,
-            recipe.servings,
-            recipe.source,
-            recipe.source_url,
-            recipe.total_time,
-            recipe.uid,
-        )
-        .execute(tx)
-        .await?;
+        if recipe.in_trash && skip_trashed_recipes() {
+            tracing::debug!("skipping trashed recipe {}", recipe.uid);
+            skip_recipe(tx, &recipe).await?;
+            return Ok(State::Equal);
+        }
 
-        Ok(())
+        insert_recipe(tx, &recipe).await?;
+
+        Ok(State::Added)
     }
 
+    /// Update a recipe, which may also be one that was skipped or hasn't been
+    /// stored yet.
     async fn on_change(
         paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
+        let stored_hash =
+            sqlx::query_scalar!("SELECT hash FROM recipe WHERE uid = $1", new_item.uid)
+                .fetch_optional(&mut *tx)
+                .await?;
+
+        // Hashes that only differ by case are the same recipe, so only the
+        // stored hash needs updating for later syncs to compare it as equal.
+        if stored_hash
+            .as_deref()
+            .is_some_and(|hash| hash.eq_ignore_ascii_case(&new_item.hash))
+        {
+            tracing::debug!(
+                "recipe {} hash is unchanged, skipping download",
+                new_item.uid
+            );
+
+            sqlx::query!(
+                "UPDATE recipe SET hash = $2 WHERE uid = $1",
+                new_item.uid,
+                new_item.hash
+            )
+            .execute(tx)
+            .await?;
+
+            return Ok(State::Equal);
+        }
+
         let recipe = paprika.recipe(&new_item.uid).await?;
 
+        if recipe.in_trash && skip_trashed_recipes() {
+            tracing::debug!("removing trashed recipe {}", recipe.uid);
+            skip_recipe(tx, &recipe).await?;
+
+            return if stored_hash.is_some() {
+                sqlx::query!("DELETE FROM recipe WHERE uid = $1", recipe.uid)
+                    .execute(tx)
+                    .await?;
+
+                Ok(State::Deleted)
+            } else {
+                Ok(State::Equal)
+            };
+        }
+
+        sqlx::query!("DELETE FROM skipped_recipe WHERE uid = $1", recipe.uid)
+            .execute(&mut *tx)
+            .await?;
+
+        if stored_hash.is_none() {
+            insert_recipe(tx, &recipe).await?;
+            return Ok(State::Added);
+        }
+
         sqlx::query!(
             "UPDATE recipe SET
                 categories = $2,
@@ -291,7 +718,11 @@ impl UpdateItem for PaprikaRecipeHash {
                 servings = $24,
                 source = $25,
                 source_url = $26,
-                total_time = $27
+                total_time = $27,
+                cook_time_minutes = $28,
+                nutritional_info = $29,
+                total_time_minutes = $30,
+                prep_time_minutes = $31
             WHERE uid = $1",
             recipe.uid,
             &recipe.categories,
@@ -319,24 +750,38 @@ impl UpdateItem for PaprikaRecipeHash {
             recipe.servings,
             recipe.source,
             recipe.source_url,
-            recipe.total_time
+            recipe.total_time,
+            duration_minutes(recipe.cook_time.as_deref()),
+            recipe.nutritional_info,
+            duration_minutes(recipe.total_time.as_deref()),
+            duration_minutes(recipe.prep_time.as_deref())
         )
         .execute(tx)
         .await?;
 
-        Ok(())
+        Ok(State::Changed)
     }
 
     async fn on_delete(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         old_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
+        let skipped = sqlx::query!("DELETE FROM skipped_recipe WHERE uid = $1", old_item.uid)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
         sqlx::query!("DELETE FROM recipe WHERE uid = $1", old_item.uid)
             .execute(tx)
             .await?;
 
-        Ok(())
+        // Skipped recipes were never stored, so removing them isn't a change.
+        Ok(if skipped > 0 {
+            State::Equal
+        } else {
+            State::Deleted
+        })
     }
 
     async fn pre_commit(
@@ -408,7 +853,7 @@ impl UpdateItem for PaprikaMeal {
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "INSERT INTO meal (uid, recipe_uid, date, meal_type, name, order_flag, type_uid) VALUES ($1, $2, $3, $4, $5, $6, $7)",
             new_item.uid,
@@ -421,14 +866,14 @@ impl UpdateItem for PaprikaMeal {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Added)
     }
 
     async fn on_change(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "UPDATE meal SET recipe_uid = $2, date = $3, meal_type = $4, name = $5, order_flag = $6, type_uid = $7 WHERE uid = $1",
             new_item.uid,
@@ -441,18 +886,18 @@ impl UpdateItem for PaprikaMeal {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Changed)
     }
 
     async fn on_delete(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         old_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!("DELETE FROM meal WHERE uid = $1", old_item.uid)
             .execute(tx)
             .await?;
-        Ok(())
+        Ok(State::Deleted)
     }
 }
 
@@ -478,7 +923,7 @@ impl UpdateItem for PaprikaGroceryItem {
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "INSERT INTO grocery_item (uid, recipe_uid, name, order_flag, purchased, aisle, ingredient, recipe, instruction, quantity, separate, aisle_uid, list_uid) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
             new_item.uid,
@@ -497,14 +942,14 @@ impl UpdateItem for PaprikaGroceryItem {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Added)
     }
 
     async fn on_change(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "UPDATE grocery_item SET recipe_uid = $2, name = $3, order_flag = $4, purchased = $5, aisle = $6, ingredient = $7, recipe = $8, instruction = $9, quantity = $10, separate = $11, aisle_uid = $12, list_uid = $13 WHERE uid = $1",
             new_item.uid,
@@ -523,18 +968,18 @@ impl UpdateItem for PaprikaGroceryItem {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Changed)
     }
 
     async fn on_delete(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         old_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!("DELETE FROM grocery_item WHERE uid = $1", old_item.uid)
             .execute(tx)
             .await?;
-        Ok(())
+        Ok(State::Deleted)
     }
 }
 
@@ -560,7 +1005,7 @@ impl UpdateItem for PaprikaAisle {
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "INSERT INTO aisle (uid, name, order_flag) VALUES ($1, $2, $3)",
             new_item.uid,
@@ -569,14 +1014,14 @@ impl UpdateItem for PaprikaAisle {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Added)
     }
 
     async fn on_change(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "UPDATE aisle SET name = $2, order_flag = $3 WHERE uid = $1",
             new_item.uid,
@@ -585,18 +1030,18 @@ impl UpdateItem for PaprikaAisle {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Changed)
     }
 
     async fn on_delete(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         old_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!("DELETE FROM aisle WHERE uid = $1", old_item.uid)
             .execute(tx)
             .await?;
-        Ok(())
+        Ok(State::Deleted)
     }
 }
 
@@ -622,7 +1067,7 @@ impl UpdateItem for PaprikaMenu {
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "INSERT INTO menu (uid, name, notes, order_flag, days) VALUES ($1, $2, $3, $4, $5)",
             new_item.uid,
@@ -633,14 +1078,14 @@ impl UpdateItem for PaprikaMenu {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Added)
     }
 
     async fn on_change(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "UPDATE menu SET name = $2, notes = $3, order_flag = $4, days = $5 WHERE uid = $1",
             new_item.uid,
@@ -651,21 +1096,99 @@ impl UpdateItem for PaprikaMenu {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Changed)
     }
 
     async fn on_delete(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         old_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!("DELETE FROM menu WHERE uid = $1", old_item.uid)
             .execute(tx)
             .await?;
-        Ok(())
+        Ok(State::Deleted)
     }
 }
 
+/// If photo contents should be downloaded and stored in the database,
+/// controlled by the `STORE_PHOTO_BLOBS` environment variable. Only photo
+/// details are stored by default.
+fn store_photo_blobs() -> bool {
+    matches!(
+        std::env::var("STORE_PHOTO_BLOBS").as_deref(),
+        Ok("1") | Ok("true")
+    )
+}
+
+/// Store the contents of a photo, verifying they match the hash. Photos
+/// without a URL or with mismatched contents are logged and skipped.
+///
+/// Contents are shared by photos with the same hash, so they are only
+/// downloaded if no other photo has already stored them.
+async fn store_photo_blob(
+    paprika: &PaprikaClient,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    photo: &PaprikaPhoto,
+) -> anyhow::Result<()> {
+    let existing = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM photo_blob WHERE hash = lower($1)) "exists!""#,
+        photo.hash
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if existing {
+        tracing::debug!("photo {} contents were already stored", photo.uid);
+        return Ok(());
+    }
+
+    let details = paprika.photo(&photo.uid).await?;
+
+    let data = match details.photo_url.filter(|photo_url| !photo_url.is_empty()) {
+        Some(photo_url) => paprika.download_photo(photo_url).await?,
+        None => {
+            tracing::warn!("photo {} has no url, not storing contents", photo.uid);
+            return Ok(());
+        }
+    };
+
+    if !data.matches_hash(&photo.hash) {
+        tracing::warn!(
+            "photo {} did not match hash {}, not storing contents",
+            photo.uid,
+            photo.hash
+        );
+        return Ok(());
+    }
+
+    sqlx::query!(
+        "INSERT INTO photo_blob (hash, bytes) VALUES (lower($1), $2)",
+        photo.hash,
+        data.data
+    )
+    .execute(tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Remove stored contents for a hash once no photo has that hash. This must
+/// be called after the photo that used it was updated or deleted.
+async fn release_photo_blob(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    hash: &str,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        "DELETE FROM photo_blob WHERE hash = lower($1) AND NOT EXISTS (SELECT 1 FROM photo WHERE lower(photo.hash) = lower($1))",
+        hash
+    )
+    .execute(tx)
+    .await?;
+
+    Ok(())
+}
+
 #[async_trait::async_trait]
 impl UpdateItem for PaprikaPhoto {
     async fn existing_items(
@@ -688,10 +1211,10 @@ impl UpdateItem for PaprikaPhoto {
     }
 
     async fn on_add(
-        _paprika: &PaprikaClient,
+        paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "INSERT INTO photo (uid, filename, recipe_uid, order_flag, name, hash) VALUES ($1, $2, $3, $4, $5, $6)",
             new_item.uid,
@@ -701,16 +1224,25 @@ impl UpdateItem for PaprikaPhoto {
             new_item.name,
             new_item.hash
         )
-        .execute(tx)
+        .execute(&mut *tx)
         .await?;
-        Ok(())
+
+        if store_photo_blobs() {
+            store_photo_blob(paprika, tx, new_item).await?;
+        }
+
+        Ok(State::Added)
     }
 
     async fn on_change(
-        _paprika: &PaprikaClient,
+        paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
+        let old_hash = sqlx::query_scalar!("SELECT hash FROM photo WHERE uid = $1", new_item.uid)
+            .fetch_one(&mut *tx)
+            .await?;
+
         sqlx::query!(
             "UPDATE photo SET filename = $2, recipe_uid = $3, order_flag = $4, name = $5, hash = $6 WHERE uid = $1",
             new_item.uid,
@@ -720,20 +1252,31 @@ impl UpdateItem for PaprikaPhoto {
             new_item.name,
             new_item.hash
         )
-        .execute(tx)
+        .execute(&mut *tx)
         .await?;
-        Ok(())
+
+        if store_photo_blobs() && !old_hash.eq_ignore_ascii_case(&new_item.hash) {
+            release_photo_blob(tx, &old_hash).await?;
+            store_photo_blob(paprika, tx, new_item).await?;
+        }
+
+        Ok(State::Changed)
     }
 
     async fn on_delete(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         old_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!("DELETE FROM photo WHERE uid = $1", old_item.uid)
-            .execute(tx)
+            .execute(&mut *tx)
             .await?;
-        Ok(())
+
+        if store_photo_blobs() {
+            release_photo_blob(tx, &old_item.hash).await?;
+        }
+
+        Ok(State::Deleted)
     }
 }
 
@@ -759,7 +1302,7 @@ impl UpdateItem for PaprikaMealType {
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "INSERT INTO meal_type (uid, name, order_flag, color, export_all_day, export_time, original_type) VALUES ($1, $2, $3, $4, $5, $6, $7)",
             new_item.uid,
@@ -772,14 +1315,14 @@ impl UpdateItem for PaprikaMealType {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Added)
     }
 
     async fn on_change(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "UPDATE meal_type SET name = $2, order_flag = $3, color = $4, export_all_day = $5, export_time = $6, original_type = $7 WHERE uid = $1",
             new_item.uid,
@@ -792,18 +1335,18 @@ impl UpdateItem for PaprikaMealType {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Changed)
     }
 
     async fn on_delete(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         old_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!("DELETE FROM meal_type WHERE uid = $1", old_item.uid)
             .execute(tx)
             .await?;
-        Ok(())
+        Ok(State::Deleted)
     }
 }
 
@@ -829,7 +1372,7 @@ impl UpdateItem for PaprikaPantryItem {
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "INSERT INTO pantry_item (uid, ingredient, aisle, expiration_date, has_expiration, in_stock, purchase_date, quantity, aisle_uid) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
             new_item.uid,
@@ -844,14 +1387,14 @@ impl UpdateItem for PaprikaPantryItem {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Added)
     }
 
     async fn on_change(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "UPDATE pantry_item SET ingredient = $2, aisle = $3, expiration_date = $4, has_expiration = $5, in_stock = $6, purchase_date = $7, quantity = $8, aisle_uid = $9 WHERE uid = $1",
             new_item.uid,
@@ -866,18 +1409,18 @@ impl UpdateItem for PaprikaPantryItem {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Changed)
     }
 
     async fn on_delete(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         old_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!("DELETE FROM pantry_item WHERE uid = $1", old_item.uid)
             .execute(tx)
             .await?;
-        Ok(())
+        Ok(State::Deleted)
     }
 }
 
@@ -904,7 +1447,7 @@ impl UpdateItem for PaprikaGroceryIngredient {
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "INSERT INTO grocery_ingredient (uid, name, aisle_uid) VALUES ($1, $2, $3)",
             new_item.uid,
@@ -913,14 +1456,14 @@ impl UpdateItem for PaprikaGroceryIngredient {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Added)
     }
 
     async fn on_change(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "UPDATE grocery_ingredient SET name = $2, aisle_uid = $3 WHERE uid = $1",
             new_item.uid,
@@ -929,21 +1472,21 @@ impl UpdateItem for PaprikaGroceryIngredient {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Changed)
     }
 
     async fn on_delete(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         old_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "DELETE FROM grocery_ingredient WHERE uid = $1",
             old_item.uid
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Deleted)
     }
 }
 
@@ -972,7 +1515,7 @@ impl UpdateItem for PaprikaGroceryList {
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "INSERT INTO grocery_list (uid, name, order_flag, is_default, reminders_list) VALUES ($1, $2, $3, $4, $5)",
             new_item.uid,
@@ -983,14 +1526,14 @@ impl UpdateItem for PaprikaGroceryList {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Added)
     }
 
     async fn on_change(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "UPDATE grocery_list SET name = $2, order_flag = $3, is_default = $4, reminders_list = $5 WHERE uid = $1",
             new_item.uid,
@@ -1001,18 +1544,18 @@ impl UpdateItem for PaprikaGroceryList {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Changed)
     }
 
     async fn on_delete(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         old_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!("DELETE FROM grocery_list WHERE uid = $1", old_item.uid)
             .execute(tx)
             .await?;
-        Ok(())
+        Ok(State::Deleted)
     }
 }
 
@@ -1038,7 +1581,7 @@ impl UpdateItem for PaprikaBookmark {
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "INSERT INTO bookmark (uid, title, url, order_flag) VALUES ($1, $2, $3, $4)",
             new_item.uid,
@@ -1048,14 +1591,14 @@ impl UpdateItem for PaprikaBookmark {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Added)
     }
 
     async fn on_change(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "UPDATE bookmark SET title = $2, url = $3, order_flag = $4 WHERE uid = $1",
             new_item.uid,
@@ -1065,18 +1608,18 @@ impl UpdateItem for PaprikaBookmark {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Changed)
     }
 
     async fn on_delete(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         old_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!("DELETE FROM bookmark WHERE uid = $1", old_item.uid)
             .execute(tx)
             .await?;
-        Ok(())
+        Ok(State::Deleted)
     }
 }
 
@@ -1105,7 +1648,7 @@ impl UpdateItem for PaprikaMenuItem {
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "INSERT INTO menu_item (uid, name, order_flag, recipe_uid, menu_uid, type_uid, day) VALUES ($1, $2, $3, $4, $5, $6, $7)",
             new_item.uid,
@@ -1118,14 +1661,14 @@ impl UpdateItem for PaprikaMenuItem {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Added)
     }
 
     async fn on_change(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "UPDATE menu_item SET name = $2, order_flag = $3, recipe_uid = $4, menu_uid = $5, type_uid = $6, day = $7 WHERE uid = $1",
             new_item.uid,
@@ -1138,18 +1681,18 @@ impl UpdateItem for PaprikaMenuItem {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Changed)
     }
 
     async fn on_delete(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         old_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!("DELETE FROM menu_item WHERE uid = $1", old_item.uid)
             .execute(tx)
             .await?;
-        Ok(())
+        Ok(State::Deleted)
     }
 }
 
@@ -1178,7 +1721,7 @@ impl UpdateItem for PaprikaCategory {
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "INSERT INTO category (uid, order_flag, name, parent_uid) VALUES ($1, $2, $3, $4)",
             new_item.uid,
@@ -1188,14 +1731,14 @@ impl UpdateItem for PaprikaCategory {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Added)
     }
 
     async fn on_change(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         new_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!(
             "UPDATE category SET order_flag = $2, name = $3, parent_uid = $4 WHERE uid = $1",
             new_item.uid,
@@ -1205,17 +1748,17 @@ impl UpdateItem for PaprikaCategory {
         )
         .execute(tx)
         .await?;
-        Ok(())
+        Ok(State::Changed)
     }
 
     async fn on_delete(
         _paprika: &PaprikaClient,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         old_item: &Self,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<State> {
         sqlx::query!("DELETE FROM category WHERE uid = $1", old_item.uid)
             .execute(tx)
             .await?;
-        Ok(())
+        Ok(State::Deleted)
     }
 }
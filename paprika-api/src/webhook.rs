@@ -0,0 +1,61 @@
+use serde::Serialize;
+
+use crate::updates::CollectionChanges;
+
+/// Longest time to wait for the webhook to respond.
+const WEBHOOK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Summary of a sync sent to the webhook.
+#[derive(Debug, Serialize)]
+struct WebhookPayload<'a> {
+    synced_at: chrono::DateTime<chrono::Utc>,
+    added: usize,
+    changed: usize,
+    deleted: usize,
+    collections: &'a [CollectionChanges],
+}
+
+/// Sends a summary of each sync that found changes to a URL.
+#[derive(Clone, Debug)]
+pub struct Webhook {
+    client: reqwest::Client,
+    url: reqwest::Url,
+}
+
+impl Webhook {
+    /// Create a webhook sending to the URL in `WEBHOOK_URL`, if set.
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        let url = match std::env::var("WEBHOOK_URL") {
+            Ok(url) if !url.trim().is_empty() => url,
+            _ => return Ok(None),
+        };
+
+        let url = reqwest::Url::parse(url.trim())?;
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()?;
+
+        Ok(Some(Self { client, url }))
+    }
+
+    /// Send the changes from a sync in the background. Failures are logged
+    /// and otherwise ignored.
+    pub fn notify(&self, collections: &[CollectionChanges]) {
+        let payload = WebhookPayload {
+            synced_at: chrono::Utc::now(),
+            added: collections.iter().map(|changes| changes.added).sum(),
+            changed: collections.iter().map(|changes| changes.changed).sum(),
+            deleted: collections.iter().map(|changes| changes.deleted).sum(),
+            collections,
+        };
+
+        let req = self.client.post(self.url.clone()).json(&payload);
+
+        tokio::spawn(async move {
+            match req.send().await.and_then(|resp| resp.error_for_status()) {
+                Ok(_resp) => tracing::debug!("delivered webhook"),
+                Err(err) => tracing::warn!("could not deliver webhook: {}", err),
+            }
+        });
+    }
+}
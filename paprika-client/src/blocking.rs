@@ -0,0 +1,212 @@
+//! Versions of client methods that block the current thread instead of
+//! needing an async runtime, available with the `blocking` feature.
+//!
+//! Blocking requests are always made directly to Paprika, without using the
+//! client's transport or concurrency limit.
+
+use crate::{
+    api_endpoint, encode_payload, endpoint_url, transport, Collection, Error, PaprikaAisle,
+    PaprikaBookmark, PaprikaCategory, PaprikaClient, PaprikaGroceryIngredient, PaprikaGroceryItem,
+    PaprikaGroceryList, PaprikaMeal, PaprikaMealType, PaprikaMenu, PaprikaMenuItem,
+    PaprikaPantryItem, PaprikaPhoto, PaprikaPhotoDetails, PaprikaRecipe, PaprikaRecipeHash,
+    PaprikaResult, PaprikaStatus, PaprikaToken, DEFAULT_USER_AGENT,
+};
+
+impl PaprikaClient {
+    /// Log in with an email and password, the same as [`PaprikaClient::login`].
+    pub fn blocking_login<S: AsRef<str>>(email: S, password: S) -> Result<Self, Error> {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(DEFAULT_USER_AGENT)
+            .gzip(true)
+            .build()?;
+
+        tracing::trace!("attempting to perform paprika login");
+        let req = client
+            .post(endpoint_url(&api_endpoint(), "account/login"))
+            .form(&[("email", email.as_ref()), ("password", password.as_ref())])
+            .send()?;
+        let body = transport::blocking_response_body(req)?;
+
+        tracing::debug!("got paprika token");
+        let token = PaprikaResult::<PaprikaToken>::from_body(&body)?.token;
+
+        Self::from_token(token)
+    }
+
+    /// Create a client from an existing token, checking that the token is
+    /// valid the same as [`PaprikaClient::token`].
+    pub fn blocking_token<S: AsRef<str>>(token: S) -> Result<Self, Error> {
+        let paprika = Self::from_token(token)?;
+
+        tracing::debug!("checking token validity");
+        match paprika.blocking_status() {
+            Ok(_status) => Ok(paprika),
+            Err(err) => Err(Self::token_error(err)),
+        }
+    }
+
+    /// The client for blocking requests, created when first needed so clients
+    /// used only from async code never start its background thread.
+    fn blocking_client(&self) -> Result<&reqwest::blocking::Client, Error> {
+        if let Some(client) = self.blocking_client.get() {
+            return Ok(client);
+        }
+
+        let client = reqwest::blocking::Client::builder()
+            .default_headers(transport::auth_headers(&self.token, &self.user_agent)?)
+            .gzip(true)
+            .build()?;
+
+        Ok(self.blocking_client.get_or_init(|| client))
+    }
+
+    /// Send a blocking request with the client's timeout, returning the body.
+    fn blocking_send(&self, req: reqwest::blocking::RequestBuilder) -> Result<Vec<u8>, Error> {
+        let req = match self.timeout {
+            Some(timeout) => req.timeout(timeout),
+            None => req,
+        };
+
+        req.send()
+            .map_err(Error::from)
+            .and_then(transport::blocking_response_body)
+            .map_err(|err| match (err, self.timeout) {
+                (Error::Network(err), Some(timeout)) if err.is_timeout() => Error::Timeout(timeout),
+                (err, _) => err,
+            })
+    }
+
+    fn blocking_json_get<S, D>(&self, endpoint: S) -> Result<D, Error>
+    where
+        S: AsRef<str>,
+        D: serde::de::DeserializeOwned,
+    {
+        let endpoint = endpoint.as_ref();
+        let client = self.blocking_client()?;
+
+        let start = std::time::Instant::now();
+        let body = self.blocking_send(client.get(endpoint_url(&self.api_endpoint, endpoint)));
+        tracing::debug!(
+            endpoint,
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "completed get request"
+        );
+        let body = body?;
+
+        PaprikaResult::from_body(&body)
+    }
+
+    fn blocking_json_post<S, D>(&self, endpoint: S, data: D) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+        D: serde::Serialize,
+    {
+        let payload = encode_payload(data)?;
+
+        let endpoint = endpoint.as_ref();
+        let client = self.blocking_client()?;
+
+        let part = reqwest::blocking::multipart::Part::bytes(payload).file_name("file");
+        let form = reqwest::blocking::multipart::Form::new().part("data", part);
+
+        let start = std::time::Instant::now();
+        let result = self.blocking_send(
+            client
+                .post(endpoint_url(&self.api_endpoint, endpoint))
+                .multipart(form),
+        );
+        tracing::debug!(
+            endpoint,
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "completed post request"
+        );
+        result?;
+
+        Ok(())
+    }
+
+    pub fn blocking_get_raw(&self, endpoint: &str) -> Result<serde_json::Value, Error> {
+        self.blocking_json_get(endpoint)
+    }
+
+    pub fn blocking_status(&self) -> Result<PaprikaStatus, Error> {
+        self.blocking_json_get("sync/status")
+    }
+
+    pub fn blocking_changed_since(
+        &self,
+        previous: &PaprikaStatus,
+    ) -> Result<Vec<Collection>, Error> {
+        let status = self.blocking_status()?;
+
+        Ok(status.changed_collections(previous))
+    }
+
+    pub fn blocking_recipes(&self) -> Result<Vec<PaprikaRecipeHash>, Error> {
+        self.blocking_json_get("sync/recipes")
+    }
+
+    pub fn blocking_recipe<S: AsRef<str>>(&self, uid: S) -> Result<PaprikaRecipe, Error> {
+        self.blocking_json_get(format!("sync/recipe/{}", uid.as_ref()))
+    }
+
+    pub fn blocking_meals(&self) -> Result<Vec<PaprikaMeal>, Error> {
+        self.blocking_json_get("sync/meals")
+    }
+
+    pub fn blocking_groceries(&self) -> Result<Vec<PaprikaGroceryItem>, Error> {
+        self.blocking_json_get("sync/groceries")
+    }
+
+    pub fn blocking_aisles(&self) -> Result<Vec<PaprikaAisle>, Error> {
+        self.blocking_json_get("sync/groceryaisles")
+    }
+
+    pub fn blocking_menus(&self) -> Result<Vec<PaprikaMenu>, Error> {
+        self.blocking_json_get("sync/menus")
+    }
+
+    pub fn blocking_menu_items(&self) -> Result<Vec<PaprikaMenuItem>, Error> {
+        self.blocking_json_get("sync/menuitems")
+    }
+
+    pub fn blocking_photos(&self) -> Result<Vec<PaprikaPhoto>, Error> {
+        self.blocking_json_get("sync/photos")
+    }
+
+    pub fn blocking_photo<S: AsRef<str>>(&self, uid: S) -> Result<PaprikaPhotoDetails, Error> {
+        self.blocking_json_get(format!("sync/photo/{}", uid.as_ref()))
+    }
+
+    pub fn blocking_meal_types(&self) -> Result<Vec<PaprikaMealType>, Error> {
+        self.blocking_json_get("sync/mealtypes")
+    }
+
+    pub fn blocking_pantry_items(&self) -> Result<Vec<PaprikaPantryItem>, Error> {
+        self.blocking_json_get("sync/pantry")
+    }
+
+    pub fn blocking_grocery_ingredients(&self) -> Result<Vec<PaprikaGroceryIngredient>, Error> {
+        self.blocking_json_get("sync/groceryingredients")
+    }
+
+    pub fn blocking_grocery_lists(&self) -> Result<Vec<PaprikaGroceryList>, Error> {
+        self.blocking_json_get("sync/grocerylists")
+    }
+
+    pub fn blocking_bookmarks(&self) -> Result<Vec<PaprikaBookmark>, Error> {
+        self.blocking_json_get("sync/bookmarks")
+    }
+
+    pub fn blocking_categories(&self) -> Result<Vec<PaprikaCategory>, Error> {
+        self.blocking_json_get("sync/categories")
+    }
+
+    pub fn blocking_upload_recipe(&self, recipe: &PaprikaRecipe) -> Result<(), Error> {
+        self.blocking_json_post(format!("sync/recipe/{}", recipe.uid), recipe)
+    }
+
+    pub fn blocking_upload_groceries(&self, items: &[PaprikaGroceryItem]) -> Result<(), Error> {
+        self.blocking_json_post("sync/groceries", items)
+    }
+}
@@ -0,0 +1,89 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::sync::Semaphore;
+
+use crate::{
+    api_endpoint, CassetteTransport, Error, HttpTransport, PaprikaClient, ReqwestTransport,
+    DEFAULT_USER_AGENT,
+};
+
+/// Builder for a [`PaprikaClient`] with an existing token.
+pub struct PaprikaClientBuilder {
+    token: String,
+    transport: Option<Arc<dyn HttpTransport>>,
+    max_concurrency: Option<usize>,
+    timeout: Option<Duration>,
+    user_agent: String,
+}
+
+impl PaprikaClientBuilder {
+    pub(crate) fn new(token: String) -> Self {
+        Self {
+            token,
+            transport: None,
+            max_concurrency: None,
+            timeout: None,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+
+    /// Send all API requests through a custom transport instead of making
+    /// authenticated requests to Paprika.
+    pub fn transport<T: HttpTransport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Limit the number of API requests in flight at once across every call
+    /// made with the client, allowing at least one. Requests are unlimited by
+    /// default.
+    pub fn max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = Some(max_concurrency);
+        self
+    }
+
+    /// Fail API requests taking longer than the timeout with
+    /// [`Error::Timeout`]. Requests have no timeout by default, and it can be
+    /// changed for some requests with [`PaprikaClient::with_timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Identify requests with a custom user agent instead of
+    /// `paprika-rs/<version>`.
+    pub fn user_agent<S: Into<String>>(mut self, user_agent: S) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Build the client without making any requests.
+    pub fn build(self) -> Result<PaprikaClient, Error> {
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => CassetteTransport::from_env(Arc::new(ReqwestTransport::with_user_agent(
+                &self.token,
+                &self.user_agent,
+            )?)),
+        };
+
+        let photo_client = reqwest::Client::builder()
+            .user_agent(&self.user_agent)
+            .build()?;
+
+        Ok(PaprikaClient {
+            transport,
+            limiter: self
+                .max_concurrency
+                .map(|permits| Arc::new(Semaphore::new(permits.max(1)))),
+            timeout: self.timeout,
+            photo_client,
+            api_endpoint: api_endpoint(),
+            #[cfg(feature = "blocking")]
+            user_agent: self.user_agent,
+            #[cfg(feature = "blocking")]
+            blocking_client: Default::default(),
+            token: self.token,
+        })
+    }
+}
@@ -0,0 +1,96 @@
+use crate::ingredient::parse_quantity;
+
+/// Units recognized after a number in a duration and their length in minutes.
+const DURATION_UNITS: &[(&[&str], f64)] = &[
+    (&["d", "day", "days"], 1440.0),
+    (&["h", "hr", "hrs", "hour", "hours"], 60.0),
+    (&["m", "min", "mins", "minute", "minutes"], 1.0),
+    (&["s", "sec", "secs", "second", "seconds"], 1.0 / 60.0),
+];
+
+/// Parse a duration written for people, such as `1 hr 30 min`, into a whole
+/// number of minutes.
+///
+/// Numbers without a unit are treated as minutes, and ranges use their lower
+/// bound. Returns `None` if no duration could be found.
+pub fn parse_duration_minutes(duration: &str) -> Option<u32> {
+    let duration = duration.trim().to_lowercase();
+
+    if let Some((hours, minutes)) = duration.split_once(':') {
+        if let (Ok(hours), Ok(minutes)) =
+            (hours.trim().parse::<u32>(), minutes.trim().parse::<u32>())
+        {
+            return hours.checked_mul(60)?.checked_add(minutes);
+        }
+    }
+
+    let mut rest = duration.as_str();
+    let mut minutes = None;
+
+    while let Some(c) = rest.chars().next() {
+        let (quantity, after) = match parse_quantity(rest) {
+            Some(parsed) => parsed,
+            None => {
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+        };
+
+        let after = after.trim_start();
+        let word_end = after
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(after.len());
+        let (word, after) = after.split_at(word_end);
+
+        let scale = if word.is_empty() {
+            Some(1.0)
+        } else {
+            DURATION_UNITS
+                .iter()
+                .find(|(names, _scale)| names.contains(&word))
+                .map(|(_names, scale)| *scale)
+        };
+
+        if let Some(scale) = scale {
+            minutes = Some(minutes.unwrap_or(0.0) + quantity * scale);
+        }
+
+        // Ranges use their lower bound, so stop at the start of the upper
+        // bound when both sides have units, such as `1 hour to 1 hour 30`.
+        let after_trimmed = after.trim_start();
+        if minutes.is_some()
+            && ["to ", "-", "–"]
+                .iter()
+                .any(|separator| after_trimmed.starts_with(separator))
+        {
+            break;
+        }
+
+        rest = after;
+    }
+
+    minutes.map(|minutes: f64| minutes.round() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration_minutes() {
+        assert_eq!(parse_duration_minutes("1 hr 30 min"), Some(90));
+        assert_eq!(parse_duration_minutes("1 1/2 hours"), Some(90));
+        assert_eq!(parse_duration_minutes("45 minutes"), Some(45));
+        assert_eq!(parse_duration_minutes("20-25 mins"), Some(20));
+        assert_eq!(parse_duration_minutes("2 to 3 hours"), Some(120));
+        assert_eq!(parse_duration_minutes("1 hour to 1 hour 30 mins"), Some(60));
+        assert_eq!(parse_duration_minutes("45 mins - 1 hour"), Some(45));
+        assert_eq!(parse_duration_minutes("1 hour 15"), Some(75));
+        assert_eq!(parse_duration_minutes("2:05"), Some(125));
+        assert_eq!(parse_duration_minutes("PT1H30M"), Some(90));
+        assert_eq!(parse_duration_minutes("90"), Some(90));
+        assert_eq!(parse_duration_minutes("overnight"), None);
+        assert_eq!(parse_duration_minutes(""), None);
+        assert_eq!(parse_duration_minutes("99999999:00"), None);
+    }
+}
@@ -0,0 +1,442 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    match_grocery_ingredient, new_uid, normalize_ingredient_name, parse_ingredients, PaprikaAisle,
+    PaprikaGroceryIngredient, PaprikaGroceryItem, PaprikaPantryItem, PaprikaRecipe,
+    ParsedIngredient,
+};
+
+/// An amount of an ingredient.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ConsolidatedQuantity {
+    /// The numeric quantity, if one could be parsed.
+    pub quantity: Option<f64>,
+    /// A recognized unit, in its canonical abbreviated form.
+    pub unit: Option<String>,
+}
+
+/// Grocery items needing the same ingredient, combined together.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ConsolidatedItem {
+    /// The normalized ingredient name shared by the items.
+    pub ingredient: String,
+    /// Quantities needed, with numeric quantities in the same unit summed.
+    pub quantities: Vec<ConsolidatedQuantity>,
+    /// Names of the recipes the items were added for.
+    pub recipes: Vec<String>,
+    /// The uids of the combined items.
+    pub item_uids: Vec<String>,
+}
+
+/// The normalized ingredient for a grocery item, falling back to the item's
+/// name if it has no ingredient.
+fn normalized_ingredient(item: &PaprikaGroceryItem) -> String {
+    if item.ingredient.trim().is_empty() {
+        normalize_ingredient_name(&item.name)
+    } else {
+        normalize_ingredient_name(&item.ingredient)
+    }
+}
+
+/// Group unpurchased grocery items that normalize to the same ingredient,
+/// returning only groups containing more than one item.
+pub fn duplicate_grocery_items(items: &[PaprikaGroceryItem]) -> Vec<Vec<PaprikaGroceryItem>> {
+    let mut groups: BTreeMap<String, Vec<PaprikaGroceryItem>> = BTreeMap::new();
+
+    for item in items.iter().filter(|item| !item.purchased) {
+        let ingredient = normalized_ingredient(item);
+        if ingredient.is_empty() {
+            continue;
+        }
+
+        groups.entry(ingredient).or_default().push(item.clone());
+    }
+
+    groups
+        .into_values()
+        .filter(|items| items.len() >= 2)
+        .collect()
+}
+
+/// Combine unpurchased grocery items that normalize to the same ingredient.
+///
+/// Numeric quantities with the same unit are summed, while quantities in
+/// other units or that could not be parsed are listed separately.
+pub fn consolidate(items: &[PaprikaGroceryItem]) -> Vec<ConsolidatedItem> {
+    let mut consolidated: BTreeMap<String, ConsolidatedItem> = BTreeMap::new();
+
+    for item in items.iter().filter(|item| !item.purchased) {
+        let ingredient = normalized_ingredient(item);
+        if ingredient.is_empty() {
+            continue;
+        }
+
+        let parsed = if item.quantity.trim().is_empty() {
+            ParsedIngredient::parse(&item.name)
+        } else {
+            ParsedIngredient::parse(&item.quantity)
+        };
+
+        let entry = consolidated
+            .entry(ingredient.clone())
+            .or_insert_with(|| ConsolidatedItem {
+                ingredient,
+                quantities: Vec::new(),
+                recipes: Vec::new(),
+                item_uids: Vec::new(),
+            });
+
+        let existing = entry.quantities.iter_mut().find(|quantity| {
+            quantity.unit == parsed.unit && quantity.quantity.is_some() == parsed.quantity.is_some()
+        });
+        match (existing, parsed.quantity) {
+            (Some(existing), Some(quantity)) => {
+                existing.quantity = existing.quantity.map(|total| total + quantity);
+            }
+            (Some(_existing), None) => (),
+            (None, quantity) => entry.quantities.push(ConsolidatedQuantity {
+                quantity,
+                unit: parsed.unit,
+            }),
+        }
+
+        if let Some(recipe) = item.recipe.as_ref().filter(|recipe| !recipe.is_empty()) {
+            if !entry.recipes.contains(recipe) {
+                entry.recipes.push(recipe.clone());
+            }
+        }
+
+        entry.item_uids.push(item.uid.clone());
+    }
+
+    consolidated.into_values().collect()
+}
+
+/// Format unpurchased grocery items as plain text, grouped under the name of
+/// their aisle.
+///
+/// Aisles and the items within them are ordered by their `order_flag`. Items
+/// in an aisle that isn't provided are listed under "Other" after all known
+/// aisles, and purchased items are omitted.
+pub fn format_grocery_list(items: &[PaprikaGroceryItem], aisles: &[PaprikaAisle]) -> String {
+    let mut aisles: Vec<_> = aisles.iter().collect();
+    aisles.sort_by_key(|aisle| aisle.order_flag);
+
+    let mut items: Vec<_> = items.iter().filter(|item| !item.purchased).collect();
+    items.sort_by_key(|item| item.order_flag);
+
+    let mut sections: Vec<(&str, Vec<&PaprikaGroceryItem>)> = aisles
+        .iter()
+        .map(|aisle| {
+            let aisle_items = items
+                .iter()
+                .filter(|item| item.aisle_uid == aisle.uid)
+                .copied()
+                .collect();
+
+            (aisle.name.as_str(), aisle_items)
+        })
+        .collect();
+
+    let other_items = items
+        .iter()
+        .filter(|item| !aisles.iter().any(|aisle| aisle.uid == item.aisle_uid))
+        .copied()
+        .collect();
+    sections.push(("Other", other_items));
+
+    sections
+        .into_iter()
+        .filter(|(_name, items)| !items.is_empty())
+        .map(|(name, items)| {
+            let mut section = format!("{}\n", name);
+            for item in items {
+                section.push_str(&format!("- {}\n", item.name));
+            }
+            section
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Names of aisles used for ingredients without a known aisle.
+const FALLBACK_AISLES: &[&str] = &["other", "miscellaneous"];
+
+/// Build new grocery items on a list for each of a recipe's ingredients,
+/// leaving out section headers.
+///
+/// Items are placed in the aisle of the matching grocery ingredient. Items
+/// without a matching ingredient use an aisle named "Other" or
+/// "Miscellaneous", or the first aisle if there is neither.
+pub fn grocery_items_for_recipe(
+    recipe: &PaprikaRecipe,
+    list_uid: &str,
+    grocery_ingredients: &[PaprikaGroceryIngredient],
+    aisles: &[PaprikaAisle],
+) -> Vec<PaprikaGroceryItem> {
+    let fallback_aisle = aisles
+        .iter()
+        .find(|aisle| FALLBACK_AISLES.contains(&aisle.name.trim().to_lowercase().as_str()))
+        .or_else(|| aisles.iter().min_by_key(|aisle| aisle.order_flag));
+
+    parse_ingredients(&recipe.ingredients)
+        .into_iter()
+        .filter(|ingredient| !ingredient.is_header && !ingredient.name.is_empty())
+        .enumerate()
+        .map(|(index, ingredient)| {
+            let grocery_ingredient =
+                match_grocery_ingredient(grocery_ingredients, &ingredient.name);
+
+            let aisle = grocery_ingredient
+                .and_then(|grocery_ingredient| grocery_ingredient.aisle_uid.as_deref())
+                .and_then(|aisle_uid| aisles.iter().find(|aisle| aisle.uid == aisle_uid))
+                .or(fallback_aisle);
+
+            let raw = ingredient.raw.trim();
+            let quantity = match (ingredient.quantity, raw.rfind(&ingredient.name)) {
+                (Some(_quantity), Some(end)) => raw[..end].trim(),
+                _ => "",
+            };
+
+            PaprikaGroceryItem {
+                uid: new_uid(),
+                recipe_uid: Some(recipe.uid.clone()),
+                name: raw.to_string(),
+                order_flag: index as i32,
+                purchased: false,
+                aisle: aisle.map(|aisle| aisle.name.clone()).unwrap_or_default(),
+                ingredient: grocery_ingredient
+                    .map(|grocery_ingredient| grocery_ingredient.name.clone())
+                    .unwrap_or_else(|| ingredient.name.clone()),
+                recipe: Some(recipe.name.clone()),
+                instruction: String::new(),
+                quantity: quantity.to_string(),
+                separate: false,
+                aisle_uid: aisle.map(|aisle| aisle.uid.clone()).unwrap_or_default(),
+                list_uid: list_uid.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Build a new grocery item on a list for restocking a pantry item, in the
+/// same aisle as the pantry item.
+pub fn grocery_item_for_pantry_item(
+    item: &PaprikaPantryItem,
+    list_uid: &str,
+) -> PaprikaGroceryItem {
+    PaprikaGroceryItem {
+        uid: new_uid(),
+        recipe_uid: None,
+        name: item.ingredient.clone(),
+        order_flag: 0,
+        purchased: false,
+        aisle: item.aisle.clone(),
+        ingredient: item.ingredient.clone(),
+        recipe: None,
+        instruction: String::new(),
+        quantity: String::new(),
+        separate: false,
+        aisle_uid: item.aisle_uid.clone(),
+        list_uid: list_uid.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grocery_item(uid: &str, ingredient: &str, purchased: bool) -> PaprikaGroceryItem {
+        PaprikaGroceryItem {
+            uid: uid.to_string(),
+            recipe_uid: None,
+            name: ingredient.to_string(),
+            order_flag: 0,
+            purchased,
+            aisle: "Produce".to_string(),
+            ingredient: ingredient.to_string(),
+            recipe: None,
+            instruction: String::new(),
+            quantity: String::new(),
+            separate: false,
+            aisle_uid: "aisle".to_string(),
+            list_uid: "list".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_consolidate() {
+        let mut flour = grocery_item("1", "flour", false);
+        flour.quantity = "2 cups".to_string();
+        flour.recipe = Some("Bread".to_string());
+        let mut more_flour = grocery_item("2", "Flour", false);
+        more_flour.quantity = "1/2 cup".to_string();
+        more_flour.recipe = Some("Cake".to_string());
+        let mut weighed_flour = grocery_item("3", "flour", false);
+        weighed_flour.quantity = "100 g".to_string();
+        weighed_flour.recipe = Some("Bread".to_string());
+
+        let items = vec![
+            flour,
+            more_flour,
+            weighed_flour,
+            grocery_item("4", "salt", false),
+            grocery_item("5", "salt", false),
+            grocery_item("6", "sugar", true),
+        ];
+
+        let consolidated = consolidate(&items);
+        assert_eq!(consolidated.len(), 2);
+
+        let flour = &consolidated[0];
+        assert_eq!(flour.ingredient, "flour");
+        assert_eq!(
+            flour.quantities,
+            vec![
+                ConsolidatedQuantity {
+                    quantity: Some(2.5),
+                    unit: Some("cup".to_string()),
+                },
+                ConsolidatedQuantity {
+                    quantity: Some(100.0),
+                    unit: Some("g".to_string()),
+                },
+            ]
+        );
+        assert_eq!(flour.recipes, vec!["Bread", "Cake"]);
+        assert_eq!(flour.item_uids, vec!["1", "2", "3"]);
+
+        let salt = &consolidated[1];
+        assert_eq!(
+            salt.quantities,
+            vec![ConsolidatedQuantity {
+                quantity: None,
+                unit: None,
+            }]
+        );
+        assert_eq!(salt.item_uids, vec!["4", "5"]);
+    }
+
+    #[test]
+    fn test_format_grocery_list() {
+        let aisles = vec![
+            PaprikaAisle {
+                uid: "dairy".to_string(),
+                name: "Dairy".to_string(),
+                order_flag: 2,
+            },
+            PaprikaAisle {
+                uid: "aisle".to_string(),
+                name: "Produce".to_string(),
+                order_flag: 1,
+            },
+        ];
+
+        let mut milk = grocery_item("3", "milk", false);
+        milk.aisle_uid = "dairy".to_string();
+        let mut salt = grocery_item("4", "salt", false);
+        salt.aisle_uid = "unknown".to_string();
+
+        let items = vec![
+            grocery_item("1", "onion", false),
+            grocery_item("2", "garlic", true),
+            milk,
+            salt,
+        ];
+
+        assert_eq!(
+            format_grocery_list(&items, &aisles),
+            "Produce\n- onion\n\nDairy\n- milk\n\nOther\n- salt\n"
+        );
+    }
+
+    #[test]
+    fn test_grocery_items_for_recipe() {
+        let mut recipe = PaprikaRecipe::new("Bread");
+        recipe.ingredients = "Dough:\n2 cups flour\n1 tsp salt\n\nwater".to_string();
+
+        let grocery_ingredients = vec![PaprikaGroceryIngredient {
+            uid: "flour".to_string(),
+            name: "Flour".to_string(),
+            aisle_uid: Some("baking".to_string()),
+        }];
+        let aisles = vec![
+            PaprikaAisle {
+                uid: "baking".to_string(),
+                name: "Baking".to_string(),
+                order_flag: 1,
+            },
+            PaprikaAisle {
+                uid: "other".to_string(),
+                name: "Other".to_string(),
+                order_flag: 2,
+            },
+        ];
+
+        let items = grocery_items_for_recipe(&recipe, "list", &grocery_ingredients, &aisles);
+        assert_eq!(items.len(), 3);
+
+        assert_eq!(items[0].name, "2 cups flour");
+        assert_eq!(items[0].quantity, "2 cups");
+        assert_eq!(items[0].ingredient, "Flour");
+        assert_eq!(items[0].aisle_uid, "baking");
+        assert_eq!(items[0].aisle, "Baking");
+
+        assert_eq!(items[1].quantity, "1 tsp");
+        assert_eq!(items[1].ingredient, "salt");
+        assert_eq!(items[1].aisle_uid, "other");
+
+        assert_eq!(items[2].quantity, "");
+        assert_eq!(items[2].ingredient, "water");
+
+        for (index, item) in items.iter().enumerate() {
+            assert_eq!(item.order_flag, index as i32);
+            assert_eq!(item.recipe.as_deref(), Some("Bread"));
+            assert_eq!(item.recipe_uid.as_deref(), Some(recipe.uid.as_str()));
+            assert_eq!(item.list_uid, "list");
+            assert!(!item.purchased);
+        }
+    }
+
+    #[test]
+    fn test_grocery_item_for_pantry_item() {
+        let pantry_item = PaprikaPantryItem {
+            uid: "pantry".to_string(),
+            ingredient: "rice".to_string(),
+            aisle: "Grains".to_string(),
+            expiration_date: None,
+            has_expiration: false,
+            in_stock: false,
+            purchase_date: chrono::Utc::now(),
+            quantity: "1 bag".to_string(),
+            aisle_uid: "grains".to_string(),
+        };
+
+        let item = grocery_item_for_pantry_item(&pantry_item, "list");
+        assert_ne!(item.uid, pantry_item.uid);
+        assert_eq!(item.name, "rice");
+        assert_eq!(item.ingredient, "rice");
+        assert_eq!(item.aisle, "Grains");
+        assert_eq!(item.aisle_uid, "grains");
+        assert_eq!(item.list_uid, "list");
+        assert!(!item.purchased);
+    }
+
+    #[test]
+    fn test_duplicate_grocery_items() {
+        let items = vec![
+            grocery_item("1", "Onions", false),
+            grocery_item("2", "onion", false),
+            grocery_item("3", "onion", true),
+            grocery_item("4", "garlic", false),
+        ];
+
+        let duplicates = duplicate_grocery_items(&items);
+        assert_eq!(duplicates.len(), 1);
+
+        let uids: Vec<_> = duplicates[0].iter().map(|item| item.uid.as_str()).collect();
+        assert_eq!(uids, vec!["1", "2"]);
+    }
+}
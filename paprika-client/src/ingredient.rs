@@ -0,0 +1,567 @@
+use serde::{Deserialize, Serialize};
+
+use crate::PaprikaGroceryIngredient;
+
+/// A single line from a recipe's ingredients, split into its parts.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ParsedIngredient {
+    /// The original line.
+    pub raw: String,
+    /// The leading quantity, using the lower bound of ranges.
+    pub quantity: Option<f64>,
+    /// A recognized unit, in its canonical abbreviated form.
+    pub unit: Option<String>,
+    /// The remainder of the line, or the header text for section headers.
+    pub name: String,
+    /// If this line is a section header instead of an ingredient.
+    pub is_header: bool,
+}
+
+/// Units recognized after a quantity and their canonical names.
+const UNITS: &[(&str, &[&str])] = &[
+    ("cup", &["cup", "cups", "c"]),
+    (
+        "tbsp",
+        &["tbsp", "tbsps", "tbs", "tablespoon", "tablespoons", "T"],
+    ),
+    ("tsp", &["tsp", "tsps", "teaspoon", "teaspoons", "t"]),
+    ("oz", &["oz", "ounce", "ounces"]),
+    ("fl oz", &["fl oz", "fluid ounce", "fluid ounces"]),
+    ("lb", &["lb", "lbs", "pound", "pounds"]),
+    ("g", &["g", "gram", "grams"]),
+    ("kg", &["kg", "kilogram", "kilograms"]),
+    (
+        "ml",
+        &[
+            "ml",
+            "milliliter",
+            "milliliters",
+            "millilitre",
+            "millilitres",
+        ],
+    ),
+    ("l", &["l", "liter", "liters", "litre", "litres"]),
+    ("pt", &["pt", "pint", "pints"]),
+    ("qt", &["qt", "quart", "quarts"]),
+    ("gal", &["gal", "gallon", "gallons"]),
+    ("pinch", &["pinch", "pinches"]),
+    ("dash", &["dash", "dashes"]),
+    ("clove", &["clove", "cloves"]),
+    ("can", &["can", "cans"]),
+    ("package", &["package", "packages", "pkg"]),
+    ("stick", &["stick", "sticks"]),
+    ("slice", &["slice", "slices"]),
+    ("bunch", &["bunch", "bunches"]),
+];
+
+const UNICODE_FRACTIONS: &[(char, f64)] = &[
+    ('¼', 0.25),
+    ('½', 0.5),
+    ('¾', 0.75),
+    ('⅓', 1.0 / 3.0),
+    ('⅔', 2.0 / 3.0),
+    ('⅛', 0.125),
+    ('⅜', 0.375),
+    ('⅝', 0.625),
+    ('⅞', 0.875),
+];
+
+/// Parse each non-empty line of a recipe's ingredients.
+pub fn parse_ingredients(ingredients: &str) -> Vec<ParsedIngredient> {
+    ingredients
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(ParsedIngredient::parse)
+        .collect()
+}
+
+impl ParsedIngredient {
+    /// Parse a single ingredient line.
+    ///
+    /// Lines ending with a colon are treated as section headers.
+    pub fn parse(line: &str) -> Self {
+        let raw = line.to_string();
+        let line = line.trim();
+
+        if let Some(header) = line.strip_suffix(':') {
+            return Self {
+                raw,
+                quantity: None,
+                unit: None,
+                name: header.trim().to_string(),
+                is_header: true,
+            };
+        }
+
+        let (quantity, rest) = match parse_quantity(line) {
+            Some((quantity, rest)) => (Some(quantity), rest),
+            None => (None, line),
+        };
+
+        let (unit, rest) = match quantity.and_then(|_| parse_unit(rest)) {
+            Some((unit, rest)) => (Some(unit.to_string()), rest),
+            None => (None, rest),
+        };
+
+        let rest = rest.trim_start();
+        let name = rest.strip_prefix("of ").unwrap_or(rest).trim().to_string();
+
+        Self {
+            raw,
+            quantity,
+            unit,
+            name,
+            is_header: false,
+        }
+    }
+
+    /// The ingredient's name normalized for comparison.
+    pub fn normalized_name(&self) -> String {
+        normalize_ingredient_name(&self.name)
+    }
+
+    /// Multiply the ingredient's quantity by a factor, replacing only the
+    /// quantity in its `raw` text so the rest of the line is kept as written.
+    /// Ingredients without a quantity are returned unchanged.
+    pub fn scale(&self, factor: f64) -> Self {
+        let mut ingredient = self.clone();
+
+        if (factor - 1.0).abs() <= f64::EPSILON {
+            return ingredient;
+        }
+
+        let line = self.raw.trim_start();
+        let (lower, upper, rest) = match parse_quantity_range(line) {
+            Some(parsed) if self.quantity.is_some() => parsed,
+            _ => return ingredient,
+        };
+
+        let start = self.raw.len() - line.len();
+        let end = self.raw.len() - rest.len();
+
+        let mut quantity = format_quantity(lower * factor);
+        if let Some(upper) = upper {
+            quantity.push('-');
+            quantity.push_str(&format_quantity(upper * factor));
+        }
+
+        ingredient.quantity = Some(lower * factor);
+        ingredient.raw.replace_range(start..end, &quantity);
+
+        ingredient
+    }
+}
+
+/// Find the number of servings in a recipe's servings text, such as `8` in
+/// `Serves 8`. Ranges like `4-6` use their lower bound, and `None` is returned
+/// if there is no positive number.
+pub fn parse_servings(servings: &str) -> Option<f64> {
+    servings
+        .char_indices()
+        .filter(|(index, _c)| {
+            !servings[..*index].ends_with(|c: char| c.is_ascii_digit() || c == '.' || c == '/')
+        })
+        .find_map(|(index, _c)| parse_quantity(&servings[index..]))
+        .map(|(quantity, _rest)| quantity)
+        .filter(|quantity| *quantity > 0.0)
+}
+
+/// Scale each non-empty line of a recipe's ingredients by a factor. Lines
+/// without a quantity, such as section headers, are returned unchanged.
+pub fn scale_ingredients(ingredients: &str, factor: f64) -> Vec<String> {
+    parse_ingredients(ingredients)
+        .iter()
+        .map(|ingredient| ingredient.scale(factor).raw)
+        .collect()
+}
+
+/// Combine ingredients with the same normalized name and unit, summing their
+/// quantities. Section headers are removed.
+///
+/// Ingredients are returned in the order they first appeared. Combined
+/// ingredients have their `raw` text rebuilt from the summed quantity.
+pub fn consolidate_ingredients(ingredients: &[ParsedIngredient]) -> Vec<ParsedIngredient> {
+    let mut consolidated: Vec<(String, ParsedIngredient)> = Vec::new();
+
+    for ingredient in ingredients
+        .iter()
+        .filter(|ingredient| !ingredient.is_header)
+    {
+        let name = ingredient.normalized_name();
+
+        let existing = consolidated.iter_mut().find(|(existing_name, existing)| {
+            *existing_name == name
+                && existing.unit == ingredient.unit
+                && existing.quantity.is_some() == ingredient.quantity.is_some()
+        });
+
+        match existing {
+            Some((_name, existing)) => {
+                if let (Some(total), Some(quantity)) = (existing.quantity, ingredient.quantity) {
+                    existing.quantity = Some(total + quantity);
+                    existing.raw = format_ingredient(existing);
+                }
+            }
+            None => consolidated.push((name, ingredient.clone())),
+        }
+    }
+
+    consolidated
+        .into_iter()
+        .map(|(_name, ingredient)| ingredient)
+        .collect()
+}
+
+/// Format an ingredient's quantity, unit, and name as a single line.
+fn format_ingredient(ingredient: &ParsedIngredient) -> String {
+    let quantity = ingredient.quantity.map(format_quantity);
+
+    quantity
+        .iter()
+        .map(String::as_str)
+        .chain(ingredient.unit.as_deref())
+        .chain(std::iter::once(ingredient.name.as_str()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Fractions written as fractions when formatting a quantity.
+const COMMON_FRACTIONS: &[(&str, f64)] = &[
+    ("1/8", 0.125),
+    ("1/4", 0.25),
+    ("1/3", 1.0 / 3.0),
+    ("3/8", 0.375),
+    ("1/2", 0.5),
+    ("5/8", 0.625),
+    ("2/3", 2.0 / 3.0),
+    ("3/4", 0.75),
+    ("7/8", 0.875),
+];
+
+/// Format a quantity for people to read, such as `1 1/2` instead of `1.5`.
+/// Quantities that aren't close to a common fraction are rounded to two
+/// decimal places.
+fn format_quantity(quantity: f64) -> String {
+    const TOLERANCE: f64 = 0.01;
+
+    let whole = quantity.trunc();
+    let fraction = quantity - whole;
+
+    if fraction < TOLERANCE {
+        return whole.to_string();
+    } else if 1.0 - fraction < TOLERANCE {
+        return (whole + 1.0).to_string();
+    }
+
+    match COMMON_FRACTIONS
+        .iter()
+        .find(|(_name, value)| (fraction - value).abs() < TOLERANCE)
+    {
+        Some((name, _value)) if whole == 0.0 => name.to_string(),
+        Some((name, _value)) => format!("{} {}", whole, name),
+        None => ((quantity * 100.0).round() / 100.0).to_string(),
+    }
+}
+
+/// Normalize an ingredient name so equivalent ingredients compare equal.
+///
+/// This lowercases the name, removes parenthetical notes and preparation
+/// instructions after a comma, and naively singularizes each word.
+pub fn normalize_ingredient_name(name: &str) -> String {
+    let mut without_notes = String::with_capacity(name.len());
+    let mut depth = 0usize;
+    for c in name.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => without_notes.push(c),
+            _ => (),
+        }
+    }
+
+    let name = without_notes.split(',').next().unwrap_or_default();
+
+    name.split_whitespace()
+        .map(|word| {
+            let word = word.to_lowercase();
+            if word.len() > 3 && word.ends_with('s') && !word.ends_with("ss") {
+                word[..word.len() - 1].to_string()
+            } else {
+                word
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Find the grocery ingredient best matching an ingredient name.
+///
+/// An exact match on the normalized names is preferred, otherwise the longest
+/// grocery ingredient whose words appear in the name is used.
+pub fn match_grocery_ingredient<'a>(
+    grocery_ingredients: &'a [PaprikaGroceryIngredient],
+    name: &str,
+) -> Option<&'a PaprikaGroceryIngredient> {
+    let name = normalize_ingredient_name(name);
+    if name.is_empty() {
+        return None;
+    }
+
+    let padded_name = format!(" {} ", name);
+
+    grocery_ingredients
+        .iter()
+        .map(|grocery_ingredient| {
+            (
+                grocery_ingredient,
+                normalize_ingredient_name(&grocery_ingredient.name),
+            )
+        })
+        .filter(|(_grocery_ingredient, grocery_name)| !grocery_name.is_empty())
+        .filter_map(|(grocery_ingredient, grocery_name)| {
+            if grocery_name == name {
+                Some((grocery_ingredient, usize::MAX))
+            } else if padded_name.contains(&format!(" {} ", grocery_name)) {
+                Some((grocery_ingredient, grocery_name.len()))
+            } else {
+                None
+            }
+        })
+        .max_by_key(|(_grocery_ingredient, score)| *score)
+        .map(|(grocery_ingredient, _score)| grocery_ingredient)
+}
+
+/// Parse a leading quantity, returning it and the remainder of the input.
+///
+/// Supports whole numbers, decimals, fractions, mixed numbers, unicode
+/// fractions, and ranges (where the lower bound is used).
+pub(crate) fn parse_quantity(input: &str) -> Option<(f64, &str)> {
+    parse_quantity_range(input).map(|(lower, _upper, rest)| (lower, rest))
+}
+
+/// Parse a leading quantity the same as [`parse_quantity`], also returning
+/// the upper bound of ranges.
+fn parse_quantity_range(input: &str) -> Option<(f64, Option<f64>, &str)> {
+    let (quantity, rest) = parse_mixed_number(input)?;
+
+    let trimmed = rest.trim_start();
+    let range_rest = trimmed
+        .strip_prefix('-')
+        .or_else(|| trimmed.strip_prefix('–'))
+        .or_else(|| trimmed.strip_prefix("to "));
+
+    match range_rest.and_then(|range_rest| parse_mixed_number(range_rest.trim_start())) {
+        Some((upper, rest)) => Some((quantity, Some(upper), rest)),
+        None => Some((quantity, None, rest)),
+    }
+}
+
+/// Parse a number that may be followed by a fraction, such as `1 1/2`.
+fn parse_mixed_number(input: &str) -> Option<(f64, &str)> {
+    let (whole, rest) = parse_number(input)?;
+
+    match parse_number(rest.trim_start()) {
+        Some((fraction, fraction_rest)) if fraction < 1.0 && whole.fract() == 0.0 => {
+            Some((whole + fraction, fraction_rest))
+        }
+        _ => Some((whole, rest)),
+    }
+}
+
+/// Parse a single number, fraction, or unicode fraction.
+fn parse_number(input: &str) -> Option<(f64, &str)> {
+    let mut chars = input.chars();
+    if let Some(value) = chars.next().and_then(unicode_fraction) {
+        return Some((value, chars.as_str()));
+    }
+
+    let end = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '/'))
+        .unwrap_or(input.len());
+    let (number, rest) = input.split_at(end);
+
+    let value = match number.split_once('/') {
+        Some((numerator, denominator)) => {
+            let numerator: f64 = numerator.parse().ok()?;
+            let denominator: f64 = denominator.parse().ok()?;
+            if denominator == 0.0 {
+                return None;
+            }
+            numerator / denominator
+        }
+        None => number.parse().ok()?,
+    };
+
+    // A whole number may be immediately followed by a unicode fraction.
+    let mut chars = rest.chars();
+    match chars.next().and_then(unicode_fraction) {
+        Some(fraction) if value.fract() == 0.0 => Some((value + fraction, chars.as_str())),
+        _ => Some((value, rest)),
+    }
+}
+
+fn unicode_fraction(c: char) -> Option<f64> {
+    UNICODE_FRACTIONS
+        .iter()
+        .find(|(fraction, _value)| *fraction == c)
+        .map(|(_fraction, value)| *value)
+}
+
+/// Parse a leading unit, returning its canonical name and the remainder.
+fn parse_unit(input: &str) -> Option<(&'static str, &str)> {
+    let input = input.trim_start();
+
+    UNITS
+        .iter()
+        .flat_map(|(canonical, names)| names.iter().map(move |name| (*canonical, *name)))
+        .filter_map(|(canonical, name)| {
+            // Single letter units are case sensitive, otherwise `T` and `t`
+            // would be indistinguishable.
+            let prefix = input.get(..name.len())?;
+            let matches = if name.len() == 1 {
+                prefix == name
+            } else {
+                prefix.eq_ignore_ascii_case(name)
+            };
+
+            let rest = &input[name.len()..];
+            let rest = rest.strip_prefix('.').unwrap_or(rest);
+            let at_boundary = rest.is_empty() || rest.starts_with(char::is_whitespace);
+
+            if matches && at_boundary {
+                Some((canonical, name.len(), rest))
+            } else {
+                None
+            }
+        })
+        .max_by_key(|(_canonical, len, _rest)| *len)
+        .map(|(canonical, _len, rest)| (canonical, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_ingredients() {
+        let ingredients = "Dough:\n1 1/2 cups flour\n\n2 eggs\nsalt to taste";
+
+        assert_eq!(
+            scale_ingredients(ingredients, 2.0),
+            vec!["Dough:", "3 cups flour", "4 eggs", "salt to taste"]
+        );
+        assert_eq!(
+            scale_ingredients("1/3 cup sugar\n  2-3 cloves garlic, minced", 2.0),
+            vec!["2/3 cup sugar", "  4-6 cloves garlic, minced"]
+        );
+        assert_eq!(
+            scale_ingredients("1 (14 oz) can tomatoes\n3 eggs", 0.5),
+            vec!["1/2 (14 oz) can tomatoes", "1 1/2 eggs"]
+        );
+        assert_eq!(
+            scale_ingredients(ingredients, 1.0),
+            vec!["Dough:", "1 1/2 cups flour", "2 eggs", "salt to taste"]
+        );
+    }
+
+    #[test]
+    fn test_parse_servings() {
+        assert_eq!(parse_servings("4"), Some(4.0));
+        assert_eq!(parse_servings("4-6"), Some(4.0));
+        assert_eq!(parse_servings("4 to 6 people"), Some(4.0));
+        assert_eq!(parse_servings("Serves 8"), Some(8.0));
+        assert_eq!(parse_servings("Makes 1½ dozen"), Some(1.5));
+        assert_eq!(parse_servings("2.5 servings"), Some(2.5));
+
+        assert_eq!(parse_servings(""), None);
+        assert_eq!(parse_servings("a crowd"), None);
+        assert_eq!(parse_servings("0"), None);
+    }
+
+    #[test]
+    fn test_parse_ingredient() {
+        let ingredient = ParsedIngredient::parse("1 1/2 cups all-purpose flour");
+        assert_eq!(ingredient.quantity, Some(1.5));
+        assert_eq!(ingredient.unit.as_deref(), Some("cup"));
+        assert_eq!(ingredient.name, "all-purpose flour");
+        assert!(!ingredient.is_header);
+
+        let ingredient = ParsedIngredient::parse("½ tsp. salt");
+        assert_eq!(ingredient.quantity, Some(0.5));
+        assert_eq!(ingredient.unit.as_deref(), Some("tsp"));
+        assert_eq!(ingredient.name, "salt");
+
+        let ingredient = ParsedIngredient::parse("2-3 cloves of garlic, minced");
+        assert_eq!(ingredient.quantity, Some(2.0));
+        assert_eq!(ingredient.unit.as_deref(), Some("clove"));
+        assert_eq!(ingredient.name, "garlic, minced");
+
+        let ingredient = ParsedIngredient::parse("3 eggs");
+        assert_eq!(ingredient.quantity, Some(3.0));
+        assert_eq!(ingredient.unit, None);
+        assert_eq!(ingredient.name, "eggs");
+
+        let ingredient = ParsedIngredient::parse("Salt and pepper to taste");
+        assert_eq!(ingredient.quantity, None);
+        assert_eq!(ingredient.unit, None);
+        assert_eq!(ingredient.name, "Salt and pepper to taste");
+
+        let ingredient = ParsedIngredient::parse("For the sauce:");
+        assert!(ingredient.is_header);
+        assert_eq!(ingredient.quantity, None);
+        assert_eq!(ingredient.name, "For the sauce");
+    }
+
+    #[test]
+    fn test_consolidate_ingredients() {
+        let ingredients = parse_ingredients(
+            "For the dough:\n1 cup flour\n2 eggs\n1/2 cup Flour\n100 g flour\nSalt\n1 egg\nsalt",
+        );
+
+        let consolidated = consolidate_ingredients(&ingredients);
+        let raw: Vec<_> = consolidated
+            .iter()
+            .map(|ingredient| ingredient.raw.as_str())
+            .collect();
+        assert_eq!(
+            raw,
+            vec!["1 1/2 cup flour", "3 eggs", "100 g flour", "Salt"]
+        );
+    }
+
+    #[test]
+    fn test_normalize_ingredient_name() {
+        assert_eq!(
+            normalize_ingredient_name("Yellow Onions, diced"),
+            "yellow onion"
+        );
+        assert_eq!(
+            normalize_ingredient_name("chicken breasts (boneless)"),
+            "chicken breast"
+        );
+        assert_eq!(normalize_ingredient_name("Swiss  cheese"), "swiss cheese");
+    }
+
+    #[test]
+    fn test_match_grocery_ingredient() {
+        let grocery_ingredients: Vec<_> = ["chicken", "chicken breast", "onion"]
+            .iter()
+            .enumerate()
+            .map(|(index, name)| PaprikaGroceryIngredient {
+                uid: index.to_string(),
+                name: name.to_string(),
+                aisle_uid: None,
+            })
+            .collect();
+
+        let matched = |name| {
+            match_grocery_ingredient(&grocery_ingredients, name)
+                .map(|grocery_ingredient| grocery_ingredient.name.as_str())
+        };
+
+        assert_eq!(matched("Onions, diced"), Some("onion"));
+        assert_eq!(matched("boneless chicken breasts"), Some("chicken breast"));
+        assert_eq!(matched("chicken thighs"), Some("chicken"));
+        assert_eq!(matched("paprika"), None);
+    }
+}
@@ -0,0 +1,242 @@
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{Error, PaprikaResult, DEFAULT_USER_AGENT};
+
+/// Performs the HTTP requests made by the client.
+///
+/// Requests are authenticated by the transport, so implementations other than
+/// [`ReqwestTransport`] may return canned responses without any network
+/// access.
+#[async_trait::async_trait]
+pub trait HttpTransport: Send + Sync {
+    /// Perform a GET request, returning the response body.
+    async fn get(&self, url: &str) -> Result<Vec<u8>, Error>;
+
+    /// Perform a POST request uploading gzipped JSON as a multipart form's
+    /// `data` file, returning the response body.
+    async fn post(&self, url: &str, payload: Vec<u8>) -> Result<Vec<u8>, Error>;
+}
+
+#[async_trait::async_trait]
+impl<T: HttpTransport + ?Sized> HttpTransport for Arc<T> {
+    async fn get(&self, url: &str) -> Result<Vec<u8>, Error> {
+        (**self).get(url).await
+    }
+
+    async fn post(&self, url: &str, payload: Vec<u8>) -> Result<Vec<u8>, Error> {
+        (**self).post(url, payload).await
+    }
+}
+
+/// The most characters of an unexpected response body to include in errors.
+const BODY_SNIPPET_LEN: usize = 200;
+
+/// Read the body of a response.
+///
+/// Unsuccessful responses containing a Paprika error are returned as
+/// [`Error::Paprika`] with the response's status. Any other unsuccessful
+/// response, such as an HTML error page from a gateway, becomes
+/// [`Error::UnexpectedResponse`] with the start of the body.
+pub(crate) async fn response_body(resp: reqwest::Response) -> Result<Vec<u8>, Error> {
+    let status = resp.status();
+    let body = resp.bytes().await?.to_vec();
+
+    check_response(status, body)
+}
+
+/// Read the body of a blocking response, the same as [`response_body`].
+#[cfg(feature = "blocking")]
+pub(crate) fn blocking_response_body(resp: reqwest::blocking::Response) -> Result<Vec<u8>, Error> {
+    let status = resp.status();
+    let body = resp.bytes()?.to_vec();
+
+    check_response(status, body)
+}
+
+fn check_response(status: reqwest::StatusCode, body: Vec<u8>) -> Result<Vec<u8>, Error> {
+    if status.is_success() {
+        return Ok(body);
+    }
+
+    if let Ok(PaprikaResult::Error(mut err)) =
+        serde_json::from_slice::<PaprikaResult<serde_json::Value>>(&body)
+    {
+        err.status = Some(status);
+        return Err(err.into());
+    }
+
+    let body_snippet = String::from_utf8_lossy(&body)
+        .trim()
+        .chars()
+        .take(BODY_SNIPPET_LEN)
+        .collect();
+
+    Err(Error::UnexpectedResponse {
+        status,
+        body_snippet,
+    })
+}
+
+/// Transport sending requests to Paprika with a token.
+///
+/// Responses compressed with gzip are transparently decompressed.
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    /// Create a transport authenticating requests with the token.
+    pub fn new(token: &str) -> Result<Self, Error> {
+        Self::with_user_agent(token, DEFAULT_USER_AGENT)
+    }
+
+    /// Create a transport authenticating requests with the token and
+    /// identifying itself with a custom user agent.
+    pub fn with_user_agent(token: &str, user_agent: &str) -> Result<Self, Error> {
+        let client = reqwest::Client::builder()
+            .default_headers(auth_headers(token, user_agent)?)
+            .gzip(true)
+            .build()?;
+
+        Ok(Self { client })
+    }
+}
+
+#[async_trait::async_trait]
+impl HttpTransport for ReqwestTransport {
+    async fn get(&self, url: &str) -> Result<Vec<u8>, Error> {
+        let resp = self.client.get(url).send().await?;
+
+        response_body(resp).await
+    }
+
+    async fn post(&self, url: &str, payload: Vec<u8>) -> Result<Vec<u8>, Error> {
+        let part = reqwest::multipart::Part::bytes(payload).file_name("file");
+        let form = reqwest::multipart::Form::default().part("data", part);
+
+        let resp = self.client.post(url).multipart(form).send().await?;
+
+        response_body(resp).await
+    }
+}
+
+/// Transport recording responses to a directory or replaying them from it.
+///
+/// Each response body is stored as its own file, named after the request
+/// method and the URL's path, such as `GET_api_v2_sync_status.json`.
+pub struct CassetteTransport {
+    dir: PathBuf,
+    inner: Option<Arc<dyn HttpTransport>>,
+}
+
+impl CassetteTransport {
+    /// Send requests through another transport, saving each response.
+    pub fn record<P: Into<PathBuf>>(dir: P, inner: Arc<dyn HttpTransport>) -> Self {
+        Self {
+            dir: dir.into(),
+            inner: Some(inner),
+        }
+    }
+
+    /// Serve previously recorded responses without making any requests.
+    pub fn replay<P: Into<PathBuf>>(dir: P) -> Self {
+        Self {
+            dir: dir.into(),
+            inner: None,
+        }
+    }
+
+    /// Wrap a transport for recording or replaying if `PAPRIKA_RECORD` or
+    /// `PAPRIKA_REPLAY` is set to a directory, preferring replay if both are.
+    pub(crate) fn from_env(inner: Arc<dyn HttpTransport>) -> Arc<dyn HttpTransport> {
+        if let Ok(dir) = std::env::var("PAPRIKA_REPLAY") {
+            tracing::info!("replaying responses from {}", dir);
+            Arc::new(Self::replay(dir))
+        } else if let Ok(dir) = std::env::var("PAPRIKA_RECORD") {
+            tracing::info!("recording responses to {}", dir);
+            Arc::new(Self::record(dir, inner))
+        } else {
+            inner
+        }
+    }
+
+    fn path(&self, method: &str, url: &str) -> PathBuf {
+        let path = url
+            .split_once("://")
+            .map(|(_scheme, rest)| rest)
+            .unwrap_or(url);
+        let path = path.split_once('/').map(|(_host, path)| path).unwrap_or("");
+
+        let name: String = path
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+
+        self.dir
+            .join(format!("{}_{}.json", method, name.trim_matches('_')))
+    }
+
+    async fn request(
+        &self,
+        method: &str,
+        url: &str,
+        payload: Option<Vec<u8>>,
+    ) -> Result<Vec<u8>, Error> {
+        let path = self.path(method, url);
+
+        let inner = match &self.inner {
+            Some(inner) => inner,
+            None => {
+                tracing::trace!("replaying response from {}", path.display());
+                return Ok(tokio::fs::read(path).await?);
+            }
+        };
+
+        let body = match payload {
+            Some(payload) => inner.post(url, payload).await?,
+            None => inner.get(url).await?,
+        };
+
+        tracing::trace!("recording response to {}", path.display());
+        tokio::fs::create_dir_all(&self.dir).await?;
+        tokio::fs::write(path, &body).await?;
+
+        Ok(body)
+    }
+}
+
+#[async_trait::async_trait]
+impl HttpTransport for CassetteTransport {
+    async fn get(&self, url: &str) -> Result<Vec<u8>, Error> {
+        self.request("GET", url, None).await
+    }
+
+    async fn post(&self, url: &str, payload: Vec<u8>) -> Result<Vec<u8>, Error> {
+        self.request("POST", url, Some(payload)).await
+    }
+}
+
+/// Headers sent with every authenticated request. Fails if the token or user
+/// agent can't be used as a header value.
+pub(crate) fn auth_headers(
+    token: &str,
+    user_agent: &str,
+) -> Result<reqwest::header::HeaderMap, Error> {
+    let mut headers = reqwest::header::HeaderMap::new();
+
+    headers.insert(
+        reqwest::header::USER_AGENT,
+        reqwest::header::HeaderValue::from_str(user_agent)?,
+    );
+
+    headers.insert(
+        reqwest::header::ACCEPT,
+        reqwest::header::HeaderValue::from_static("application/json"),
+    );
+
+    let mut auth_value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))?;
+    auth_value.set_sensitive(true);
+    headers.insert(reqwest::header::AUTHORIZATION, auth_value);
+
+    Ok(headers)
+}